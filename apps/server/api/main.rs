@@ -1,18 +1,278 @@
+use std::collections::{BTreeMap, HashMap, hash_map::RandomState};
+use std::convert::Infallible;
+use std::hash::BuildHasher;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
 use axum::{
     Json, Router,
-    http::{StatusCode, header},
-    response::IntoResponse,
+    body::Body,
+    extract::{DefaultBodyLimit, FromRequestParts, Query, Request, State},
+    http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode, header, request::Parts},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
     routing::{get, post},
 };
 use dotenvy::dotenv;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
 use tower::ServiceBuilder;
-use tower_http::cors::{Any, CorsLayer};
+use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, Any, CorsLayer};
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
 use validator::Validate;
 use vercel_runtime::Error;
 use vercel_runtime::axum::VercelLayer;
-use x2_colon_api::parser::{ParseOutput, calculate_durations, clean_script};
+use x2_colon_api::audacity::parse_audacity;
+use x2_colon_api::cache::{CacheStats, ParseCache};
+use x2_colon_api::coverage::{Coverage, measure_coverage};
+use x2_colon_api::explain::{Explanation, explain_durations};
+use x2_colon_api::export::{ffmetadata_chapters, markers_csv};
+use x2_colon_api::labels::{LabelGroup, group_by_label, speaker_totals};
+use x2_colon_api::parser::{
+    BoundsPolicy, Budget, CoveredSpan, DEFAULT_BAR_WIDTH, DEFAULT_BUCKET_BOUNDS, DurationDelta, DurationResult, DurationStats, ErrorCategory, FormatLocale, FrameRate,
+    NO_TIMESTAMPS_MESSAGE, ParseOptions, ParseOutput, ParsedScript, ScriptError, RANGE_CONNECTORS, RANGE_SEPARATORS, RoundMode, SUPPORTED_FORMATS, Severity, SortOrder,
+    OrderCheck, RemovedSpan, TextEdit, calculate_durations, check_order, calculate_line_durations, calculate_total, clean_script,
+    WORD_CONNECTORS, longest_span, split_points,
+};
+use x2_colon_api::reading::{ReadingEstimate, estimate_reading};
+use x2_colon_api::srt::parse_srt;
+use x2_colon_api::transcript::parse_transcript;
+
+// Request limits, also reported by /info
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+const MAX_LINES: usize = 10_000;
+const MAX_SCAN_CANDIDATES: usize = 100_000;
+const MAX_BATCH_ITEMS: usize = 100;
+const MAX_BATCH_ITEM_BYTES: usize = 64 * 1024;
+
+// How long a session token stays usable after /session
+const SESSION_TTL: Duration = Duration::from_secs(15 * 60);
+// Sessions kept at once unless X2_MAX_SESSIONS says otherwise
+const DEFAULT_MAX_SESSIONS: usize = 256;
+
+struct Session {
+    script: Arc<ParsedScript>,
+    created: Instant,
+    last_used: Instant,
+}
+
+// Parsed scripts shared across requests, keyed by an opaque token. At
+// `capacity` the least recently used session makes room for a new one
+struct SessionStore {
+    sessions: Mutex<HashMap<String, Session>>,
+    issued: AtomicU64,
+    capacity: usize,
+}
+
+// Count of 400 responses per error category; nothing about the request is kept
+struct ErrorStats {
+    counts: [AtomicU64; ErrorCategory::ALL.len()],
+}
+
+impl Default for ErrorStats {
+    fn default() -> Self {
+        ErrorStats {
+            counts: std::array::from_fn(|_| AtomicU64::new(0)),
+        }
+    }
+}
+
+impl ErrorStats {
+    fn record(&self, category: ErrorCategory) {
+        self.counts[category as usize].fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> serde_json::Value {
+        let categories: serde_json::Map<String, serde_json::Value> = ErrorCategory::ALL
+            .iter()
+            .map(|&category| {
+                let count = self.counts[category as usize].load(Ordering::Relaxed);
+                (category.as_str().to_string(), count.into())
+            })
+            .collect();
+        let total: u64 = self.counts.iter().map(|count| count.load(Ordering::Relaxed)).sum();
+        json!({ "total": total, "categories": categories })
+    }
+}
+
+// A handler's failure: the status and message sent back, plus the category
+// /stats/errors counts a 400 under, attached to the response as an extension
+struct ApiError {
+    status: StatusCode,
+    message: String,
+    category: ErrorCategory,
+}
+
+impl ApiError {
+    // A parser error, counted under the category it was raised with
+    fn parse(error: ScriptError) -> Self {
+        ApiError {
+            status: StatusCode::BAD_REQUEST,
+            category: error.category,
+            message: error.message,
+        }
+    }
+
+    fn no_timestamps() -> Self {
+        ApiError {
+            status: StatusCode::BAD_REQUEST,
+            message: NO_TIMESTAMPS_MESSAGE.to_string(),
+            category: ErrorCategory::NoTimestamps,
+        }
+    }
+}
+
+// Errors that are not about the script, such as validation failures
+impl From<(StatusCode, String)> for ApiError {
+    fn from((status, message): (StatusCode, String)) -> Self {
+        ApiError { status, message, category: ErrorCategory::Other }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let mut response = (self.status, self.message).into_response();
+        response.extensions_mut().insert(self.category);
+        response
+    }
+}
+
+struct AppState {
+    sessions: SessionStore,
+    cache: ParseCache,
+    errors: ErrorStats,
+    // X2_FRAME_RATE, used when a request gives no frame rate
+    default_frame_rate: Option<FrameRate>,
+    // X2_SELFTEST_TOKEN; /selftest and /stats/errors are disabled without it
+    selftest_token: Option<String>,
+}
+
+type SharedState = Arc<AppState>;
+
+// Read an optional numeric setting from the environment, failing on bad values
+fn env_number<T: std::str::FromStr>(name: &str, default: T) -> Result<T, Error> {
+    match std::env::var(name) {
+        Ok(value) => value
+            .trim()
+            .parse()
+            .map_err(|_| format!("Invalid {}: {}", name, value).into()),
+        Err(_) => Ok(default),
+    }
+}
+
+impl SessionStore {
+    fn new(capacity: usize) -> Self {
+        SessionStore {
+            sessions: Mutex::new(HashMap::new()),
+            issued: AtomicU64::new(0),
+            capacity,
+        }
+    }
+
+    fn new_token(&self) -> String {
+        let counter = self.issued.fetch_add(1, Ordering::Relaxed);
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos());
+        // RandomState is keyed from OS randomness, which makes tokens unguessable
+        let high = RandomState::new().hash_one((counter, nanos, 0u8));
+        let low = RandomState::new().hash_one((counter, nanos, 1u8));
+        format!("{:016x}{:016x}", high, low)
+    }
+
+    fn insert(&self, script: ParsedScript) -> String {
+        let token = self.new_token();
+        let mut sessions = self.sessions.lock().unwrap();
+        // Keep expired sessions for one more TTL so their tokens still answer 410
+        sessions.retain(|_, session| session.created.elapsed() < SESSION_TTL * 2);
+        while sessions.len() >= self.capacity.max(1) {
+            let Some(oldest) = sessions
+                .iter()
+                .min_by_key(|(_, session)| session.last_used)
+                .map(|(token, _)| token.clone())
+            else {
+                break;
+            };
+            sessions.remove(&oldest);
+        }
+        let now = Instant::now();
+        sessions.insert(
+            token.clone(),
+            Session {
+                script: Arc::new(script),
+                created: now,
+                last_used: now,
+            },
+        );
+        token
+    }
+
+    fn get(&self, token: &str) -> Result<Arc<ParsedScript>, (StatusCode, String)> {
+        let mut sessions = self.sessions.lock().unwrap();
+        match sessions.get_mut(token) {
+            Some(session) if session.created.elapsed() < SESSION_TTL => {
+                session.last_used = Instant::now();
+                Ok(session.script.clone())
+            }
+            Some(_) => Err((StatusCode::GONE, "Session token has expired".to_string())),
+            None => Err((StatusCode::NOT_FOUND, "Unknown session token".to_string())),
+        }
+    }
+}
+
+// Use the session's parsed script when a token is given, otherwise parse the raw body.
+// The request's options always apply: a session scanned with others is scanned again
+fn resolve_script(
+    sessions: &SessionStore,
+    content: Option<&str>,
+    token: Option<&str>,
+    options: &ParseOptions,
+) -> Result<Arc<ParsedScript>, (StatusCode, String)> {
+    match (token, content) {
+        (Some(_), Some(_)) => Err((
+            StatusCode::BAD_REQUEST,
+            "Send either the script content or a session token, not both".to_string(),
+        )),
+        (Some(token), None) => {
+            let script = sessions.get(token)?;
+            if script.options() == options {
+                Ok(script)
+            } else {
+                Ok(Arc::new(ParsedScript::parse(script.input(), options)))
+            }
+        }
+        (None, Some(content)) => {
+            check_line_limit(content)?;
+            Ok(Arc::new(ParsedScript::parse(content, options)))
+        }
+        (None, None) => Err((
+            StatusCode::BAD_REQUEST,
+            "Either the script content or a session token is required".to_string(),
+        )),
+    }
+}
+
+// Reject bodies that are not UTF-8 up front with a clear message, rather than
+// the JSON extractor's generic one; scripts are only ever parsed as text
+async fn require_utf8(request: Request, next: Next) -> Result<Response, (StatusCode, String)> {
+    let (parts, body) = request.into_parts();
+    let bytes = axum::body::to_bytes(body, MAX_BODY_BYTES).await.map_err(|_| {
+        (
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("Request body exceeds the maximum of {} bytes", MAX_BODY_BYTES),
+        )
+    })?;
+    if let Err(e) = std::str::from_utf8(&bytes) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Request body must be valid UTF-8 (invalid byte at offset {})", e.valid_up_to()),
+        ));
+    }
+    Ok(next.run(Request::from_parts(parts, Body::from(bytes))).await)
+}
 
 async fn favicon() -> impl IntoResponse {
     (
@@ -25,45 +285,1484 @@ async fn hello() -> impl IntoResponse {
     Json(json!({ "message": "Welcome to x2-colon!" }))
 }
 
+async fn info(State(state): State<SharedState>) -> impl IntoResponse {
+    Json(json!({
+        "name": env!("CARGO_PKG_NAME"),
+        "version": env!("CARGO_PKG_VERSION"),
+        "formats": SUPPORTED_FORMATS,
+        "separators": RANGE_SEPARATORS,
+        "connectors": RANGE_CONNECTORS,
+        "word_connectors": WORD_CONNECTORS,
+        "options": [
+            "lenient",
+            "min_duration_secs",
+            "allow_empty",
+            "bare_durations",
+            "max_duration_secs",
+            "max_duration_severity",
+            "max_total_secs",
+            "bounds",
+            "warn_large_minutes",
+            "overflow_seconds",
+            "zero_length",
+            "unicode_digits",
+            "section_prefix",
+            "ungroup",
+            "expressions",
+            "skip_malformed",
+            "skip_invalid",
+            "word_connectors",
+            "speed_factors",
+            "multipliers",
+            "hh_mm",
+            "decimal_comma",
+            "loose_spacing",
+            "multiline_ranges",
+            "range_lists",
+            "interval_notation",
+            "skip_code_fences",
+            "nudges",
+            "frame_ranges",
+            "frame_rate",
+            "iso8601",
+            "decimal_minutes",
+            "endpoints",
+            "offset_secs",
+            "grouping_separator",
+            "decimal_separator",
+            "pad_minutes",
+            "target_secs",
+            "target_per_line_secs",
+            "total_label",
+            "histogram",
+            "bucket_bounds_secs",
+            "stats",
+            "percentiles",
+            "bars",
+            "bar_width",
+            "round_total_to_secs",
+            "round_total_mode",
+            "sort",
+            "compact",
+        ],
+        "limits": {
+            "max_body_bytes": MAX_BODY_BYTES,
+            "max_lines": MAX_LINES,
+            "max_scan_candidates": MAX_SCAN_CANDIDATES,
+            "max_batch_items": MAX_BATCH_ITEMS,
+            "max_batch_item_bytes": MAX_BATCH_ITEM_BYTES,
+            "max_sessions": state.sessions.capacity,
+        },
+    }))
+}
+
+fn check_line_limit(input: &str) -> Result<(), (StatusCode, String)> {
+    if input.lines().count() > MAX_LINES {
+        return Err((
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("Input exceeds the maximum of {} lines", MAX_LINES),
+        ));
+    }
+    Ok(())
+}
+
+// Parser options accepted by every endpoint that parses a script
+#[derive(Deserialize)]
+struct RequestOptions {
+    // Turn on the tolerant preset of `ParseOptions::lenient` on top of the rest
+    #[serde(default)]
+    lenient: bool,
+    #[serde(default)]
+    min_duration_secs: Option<u32>,
+    #[serde(default)]
+    bare_durations: bool,
+    #[serde(default)]
+    max_duration_secs: Option<u32>,
+    #[serde(default)]
+    max_duration_severity: Severity,
+    #[serde(default)]
+    max_total_secs: Option<u32>,
+    // "strict", "lenient" or {"custom": {"max_minutes": .., "max_seconds": ..}}
+    #[serde(default)]
+    bounds: BoundsPolicy,
+    #[serde(default)]
+    warn_large_minutes: bool,
+    #[serde(default)]
+    overflow_seconds: bool,
+    // "warning" or "error" flags ranges with zero length
+    #[serde(default)]
+    zero_length: Option<Severity>,
+    #[serde(default)]
+    unicode_digits: bool,
+    #[serde(default)]
+    section_prefix: Option<String>,
+    #[serde(default)]
+    ungroup: bool,
+    #[serde(default)]
+    expressions: bool,
+    // Skip malformed brackets with a warning instead of failing the request
+    #[serde(default)]
+    skip_malformed: bool,
+    // Drop invalid ranges, such as an end before the start, with a warning
+    #[serde(default)]
+    skip_invalid: bool,
+    // Also join ranges with "and" or "&", like "+"
+    #[serde(default)]
+    word_connectors: bool,
+    #[serde(default)]
+    speed_factors: bool,
+    #[serde(default)]
+    multipliers: bool,
+    // Read two-field times as H:MM rather than M:SS
+    #[serde(default)]
+    hh_mm: bool,
+    // Accept `@0,5x`; defaults from Accept-Language
+    #[serde(default)]
+    decimal_comma: Option<bool>,
+    #[serde(default)]
+    loose_spacing: bool,
+    #[serde(default)]
+    multiline_ranges: bool,
+    #[serde(default)]
+    range_lists: bool,
+    #[serde(default)]
+    interval_notation: bool,
+    #[serde(default)]
+    skip_code_fences: bool,
+    #[serde(default)]
+    nudges: bool,
+    #[serde(default)]
+    frame_ranges: bool,
+    #[serde(default)]
+    frame_rate: Option<FrameRate>,
+}
+
+// Locales writing decimals with a comma, by primary language subtag, with the
+// thousands separator they group digits with. Regions of these languages that
+// use a decimal point are listed in DECIMAL_POINT_REGIONS and get no defaults;
+// English and any language not listed keep the plain defaults.
+const COMMA_DECIMAL_LANGUAGES: &[(&str, char)] = &[
+    ("cs", '\u{a0}'),
+    ("da", '.'),
+    ("de", '.'),
+    ("es", '.'),
+    ("fi", '\u{a0}'),
+    ("fr", '\u{a0}'),
+    ("id", '.'),
+    ("it", '.'),
+    ("nb", '\u{a0}'),
+    ("nl", '.'),
+    ("pl", '\u{a0}'),
+    ("pt", '.'),
+    ("ru", '\u{a0}'),
+    ("sv", '\u{a0}'),
+    ("tr", '.'),
+    ("uk", '\u{a0}'),
+];
+const DECIMAL_POINT_REGIONS: &[&str] = &["de-ch", "de-li", "es-mx", "es-us", "fr-ch", "it-ch"];
+
+// Defaults inferred from the client's preferred Accept-Language, which explicit
+// request options override. They only affect the decimal mark accepted in
+// speed factors and separators in formatted strings, never the seconds.
+#[derive(Debug, Clone, Copy, Default)]
+struct Locale {
+    decimal_comma: bool,
+    grouping_separator: Option<char>,
+}
+
+impl Locale {
+    fn from_tag(tag: &str) -> Self {
+        let tag = tag.trim().to_ascii_lowercase().replace('_', "-");
+        if DECIMAL_POINT_REGIONS.iter().any(|region| tag.starts_with(region)) {
+            return Locale::default();
+        }
+        let language = tag.split('-').next().unwrap_or_default();
+        match COMMA_DECIMAL_LANGUAGES.iter().find(|(code, _)| *code == language) {
+            Some(&(_, separator)) => Locale {
+                decimal_comma: true,
+                grouping_separator: Some(separator),
+            },
+            None => Locale::default(),
+        }
+    }
+
+    // The language with the highest quality value, the first listed on a tie
+    fn from_accept_language(header: &str) -> Self {
+        let mut best: Option<(&str, f32)> = None;
+        for entry in header.split(',') {
+            let mut parts = entry.split(';');
+            let tag = parts.next().unwrap_or_default().trim();
+            let quality = parts
+                .find_map(|param| param.trim().strip_prefix("q="))
+                .and_then(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            if tag.is_empty() || tag == "*" || quality <= 0.0 {
+                continue;
+            }
+            if best.is_none_or(|(_, top)| quality > top) {
+                best = Some((tag, quality));
+            }
+        }
+        best.map_or_else(Locale::default, |(tag, _)| Locale::from_tag(tag))
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for Locale {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Infallible> {
+        Ok(parts
+            .headers
+            .get(header::ACCEPT_LANGUAGE)
+            .and_then(|value| value.to_str().ok())
+            .map_or_else(Locale::default, Locale::from_accept_language))
+    }
+}
+
+impl RequestOptions {
+    fn parse_options(&self, state: &AppState, locale: &Locale) -> ParseOptions {
+        let options = ParseOptions {
+            min_duration_secs: self.min_duration_secs,
+            bare_durations: self.bare_durations,
+            max_duration_secs: self.max_duration_secs,
+            max_duration_severity: self.max_duration_severity,
+            max_total_secs: self.max_total_secs,
+            bounds: self.bounds,
+            warn_large_minutes: self.warn_large_minutes,
+            overflow_seconds: self.overflow_seconds,
+            zero_length: self.zero_length,
+            unicode_digits: self.unicode_digits,
+            section_prefix: self.section_prefix.clone(),
+            ungroup: self.ungroup,
+            expressions: self.expressions,
+            skip_malformed: self.skip_malformed,
+            skip_invalid: self.skip_invalid,
+            word_connectors: self.word_connectors,
+            speed_factors: self.speed_factors,
+            multipliers: self.multipliers,
+            hh_mm: self.hh_mm,
+            decimal_comma: self.decimal_comma.unwrap_or(locale.decimal_comma),
+            loose_spacing: self.loose_spacing,
+            multiline_ranges: self.multiline_ranges,
+            range_lists: self.range_lists,
+            interval_notation: self.interval_notation,
+            skip_code_fences: self.skip_code_fences,
+            nudges: self.nudges,
+            frame_ranges: self.frame_ranges,
+            frame_rate: self.frame_rate.or(state.default_frame_rate),
+            max_candidates: Some(MAX_SCAN_CANDIDATES),
+        };
+        if self.lenient { options.with_lenient() } else { options }
+    }
+}
+
 #[derive(Deserialize, Validate)]
 struct TimeRequest {
     #[validate(length(min = 2))]
-    content: String,
+    content: Option<String>,
+    token: Option<String>,
+    // Script already split into lines; each element is parsed on its own
+    lines: Option<Vec<String>>,
+    // Answer 200 with zero totals instead of 400 when no ranges are found
+    #[serde(default)]
+    allow_empty: bool,
+    // Add ISO 8601 renderings (PT1M30S) next to each formatted duration
+    #[serde(default)]
+    iso8601: bool,
+    // Add decimal minutes (1.5 for 90 seconds) next to each duration
+    #[serde(default)]
+    decimal_minutes: bool,
+    // Add each line's `start` and `end` as durations from zero
+    #[serde(default)]
+    endpoints: bool,
+    // Shift reported start and end times (endpoints, compact) by this many seconds
+    #[serde(default)]
+    offset_secs: Option<u32>,
+    // Thousands separator for long minute counts in formatted strings; defaults from Accept-Language
+    #[serde(default)]
+    grouping_separator: Option<char>,
+    // Decimal separator for fractional strings such as `minutes_format`; defaults from Accept-Language
+    #[serde(default)]
+    decimal_separator: Option<char>,
+    // Render at least two minute digits in formatted strings (00:05 instead of 0:05)
+    #[serde(default)]
+    pad_minutes: bool,
+    // Name for the grand total; adds `totals` listing section subtotals and the total
+    #[serde(default)]
+    total_label: Option<String>,
+    // Target runtime; adds the signed time left (or over) as `remaining`
+    #[serde(default)]
+    target_secs: Option<u32>,
+    // Per-line target; adds each line's signed pad (+) or trim (-) as `target_delta`
+    #[serde(default)]
+    target_per_line_secs: Option<u32>,
+    // Count lines per duration bucket into `histogram`
+    #[serde(default)]
+    histogram: bool,
+    // Boundaries between buckets in seconds, instead of the defaults; implies `histogram`
+    #[serde(default)]
+    bucket_bounds_secs: Option<Vec<u32>>,
+    // Add `stats`: line count, shortest, longest and median line
+    #[serde(default)]
+    stats: bool,
+    // Percentiles (e.g. [90, 95]) of the line durations to add to `stats`; implies `stats`
+    #[serde(default)]
+    percentiles: Option<Vec<f64>>,
+    // Add a proportional bar (█████░░░░░) to each line, scaled to the longest line
+    #[serde(default)]
+    bars: bool,
+    // Bar length in characters instead of the default; implies `bars`
+    #[serde(default)]
+    bar_width: Option<usize>,
+    // Add `rounded_total`, the total rounded to a multiple of this many seconds
+    #[serde(default)]
+    round_total_to_secs: Option<u32>,
+    // "up" (default) or "nearest"
+    #[serde(default)]
+    round_total_mode: RoundMode,
+    // Sort lines by duration ("asc" or "desc"), keeping the original index in `order`
+    #[serde(default)]
+    sort: Option<SortOrder>,
+    // Positional `[id, start, end, seconds]` lines instead of objects
+    #[serde(default)]
+    compact: bool,
+    #[serde(flatten)]
+    options: RequestOptions,
 }
 
 #[derive(Deserialize, Validate)]
 struct CleanRequest {
     #[validate(length(min = 1))]
-    script: String,
+    script: Option<String>,
+    token: Option<String>,
+    // Return the removals as text edits instead of the whole cleaned script
+    #[serde(default)]
+    edits: bool,
+    // Remove only malformed timestamp-like text, keeping valid ranges
+    #[serde(default)]
+    malformed_only: bool,
+    // Stop after this many removals, keeping the rest as written
+    #[serde(default)]
+    preview: Option<usize>,
+    // Also list where each removal lies in the original script, in char offsets
+    #[serde(default)]
+    removed_spans: bool,
+    #[serde(flatten)]
+    options: RequestOptions,
+}
+
+// Requests made with the returned token reuse this scan when they send the
+// same options; with other options the stored script is scanned again
+#[derive(Deserialize, Validate)]
+struct SessionRequest {
+    #[validate(length(min = 1))]
+    content: String,
+    #[serde(flatten)]
+    options: RequestOptions,
+}
+
+// A script given as content or a session token, for /normalize
+#[derive(Deserialize, Validate)]
+struct NormalizeRequest {
+    #[validate(length(min = 1))]
+    content: Option<String>,
+    token: Option<String>,
+    #[serde(flatten)]
+    options: RequestOptions,
+}
+
+#[derive(Serialize)]
+struct NormalizeResponse {
+    normalized: String,
+    changed: bool,
+}
+
+#[derive(Deserialize, Validate)]
+struct StatsRequest {
+    #[validate(length(min = 2))]
+    content: Option<String>,
+    token: Option<String>,
+    // Percentiles to report besides the median, e.g. [90, 95]
+    #[serde(default)]
+    percentiles: Vec<f64>,
+    #[serde(flatten)]
+    options: RequestOptions,
+}
+
+#[derive(Serialize)]
+struct SessionResponse {
+    token: String,
+    expires_in_secs: u64,
+    ranges: usize,
+}
+
+#[derive(Deserialize, Validate)]
+struct SrtRequest {
+    #[validate(length(min = 1))]
+    content: String,
+    #[serde(default)]
+    strict: bool,
+}
+
+#[derive(Deserialize, Validate)]
+struct AudacityRequest {
+    #[validate(length(min = 1))]
+    content: String,
+}
+
+#[derive(Deserialize, Validate)]
+struct TranscriptRequest {
+    #[validate(length(min = 1))]
+    content: String,
+    // End of the last line; left open when missing
+    #[serde(default)]
+    end_secs: Option<u32>,
+}
+
+#[derive(Deserialize, Validate)]
+struct TotalRequest {
+    #[validate(length(min = 2))]
+    content: String,
+    #[serde(default)]
+    allow_empty: bool,
+    #[serde(flatten)]
+    options: RequestOptions,
+}
+
+#[derive(Deserialize, Validate)]
+struct BudgetRequest {
+    #[validate(length(min = 2))]
+    content: String,
+    // Runtime to plan towards
+    target_secs: u32,
+    #[serde(flatten)]
+    options: RequestOptions,
+}
+
+// A script and options, for endpoints that need nothing else
+#[derive(Deserialize, Validate)]
+struct ScriptRequest {
+    #[validate(length(min = 2))]
+    content: String,
+    #[serde(flatten)]
+    options: RequestOptions,
+}
+
+#[derive(Deserialize, Validate)]
+struct LabelGroupsRequest {
+    #[validate(length(min = 2))]
+    content: String,
+    // Separator between the parts of a label, e.g. " - " in "Act 1 - Scene 2"
+    delimiter: String,
+    // How many leading parts form the group, 1 by default
+    #[serde(default)]
+    depth: Option<usize>,
+    #[serde(flatten)]
+    options: RequestOptions,
+}
+
+// Typical narration pace, used when a reading-time request gives no wpm
+const DEFAULT_WPM: u32 = 150;
+
+#[derive(Deserialize, Validate)]
+struct ReadingRequest {
+    #[validate(length(min = 2))]
+    content: String,
+    #[serde(default)]
+    wpm: Option<u32>,
+    #[serde(flatten)]
+    options: RequestOptions,
+}
+
+#[derive(Deserialize, Validate)]
+struct MarkersRequest {
+    #[validate(length(min = 2))]
+    content: String,
+    // Falls back to X2_FRAME_RATE
+    frame_rate: Option<f64>,
+}
+
+#[derive(Deserialize, Validate)]
+struct CompareRequest {
+    #[validate(length(min = 1))]
+    a: String,
+    #[validate(length(min = 1))]
+    b: String,
+    #[serde(flatten)]
+    options: RequestOptions,
+}
+
+#[derive(Serialize)]
+struct CompareSide {
+    total: DurationResult,
+    lines: usize,
+}
+
+#[derive(Serialize)]
+struct CompareResponse {
+    a: CompareSide,
+    b: CompareSide,
+    // Change from `a` to `b`; negative when `b` is shorter
+    delta: DurationDelta,
+    line_delta: i64,
+}
+
+// Upper bound on /split parts, so one request cannot ask for millions of points
+const MAX_SPLIT_PARTS: u32 = 1000;
+
+// Page of lines returned by /timestamp; all lines when no limit is given
+#[derive(Deserialize)]
+struct PageQuery {
+    #[serde(default)]
+    offset: usize,
+    limit: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct SplitQuery {
+    n: u32,
+}
+
+#[derive(Deserialize, Validate)]
+struct SplitRequest {
+    #[validate(length(min = 2))]
+    content: String,
+    #[serde(flatten)]
+    options: RequestOptions,
+}
+
+#[derive(Serialize)]
+struct SplitResponse {
+    total: DurationResult,
+    parts: u32,
+    splits: Vec<DurationResult>,
+}
+
+// A result with the malformed spans `skip_malformed` passed over, for the
+// endpoints whose library result has no room for them
+#[derive(Serialize)]
+struct WithWarnings<T> {
+    #[serde(flatten)]
+    result: T,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    warnings: Option<Vec<String>>,
+}
+
+impl<T> WithWarnings<T> {
+    fn new(result: T, skipped: Vec<String>) -> Self {
+        WithWarnings {
+            result,
+            warnings: (!skipped.is_empty()).then_some(skipped),
+        }
+    }
+}
+
+// Bodies that are a bare list, map or text cannot carry `warnings`, so each
+// skipped message goes in its own header instead, escaped to visible ASCII
+const WARNING_HEADER: &str = "x-warning";
+
+fn warning_headers(skipped: &[String]) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    for message in skipped {
+        if let Ok(value) = HeaderValue::from_str(&message.escape_default().to_string()) {
+            headers.append(WARNING_HEADER, value);
+        }
+    }
+    headers
 }
 
 #[derive(Serialize)]
 struct CleanResponse {
-    cleaned: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cleaned: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    edits: Option<Vec<TextEdit>>,
+    original: String,
+    // False when cleaning left the script as it was, so clients can skip the update
+    changed: bool,
+    // Ranges a preview left in place
+    #[serde(skip_serializing_if = "Option::is_none")]
+    remaining: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    removed_spans: Option<Vec<RemovedSpan>>,
 }
 
-async fn timestamp(Json(payload): Json<TimeRequest>) -> Result<Json<ParseOutput>, (StatusCode, String)> {
+async fn timestamp(
+    State(state): State<SharedState>,
+    locale: Locale,
+    Query(page): Query<PageQuery>,
+    Json(payload): Json<TimeRequest>,
+) -> Result<Response, ApiError> {
     payload
         .validate()
         .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    let options = payload.options.parse_options(&state, &locale);
 
-    let result = calculate_durations(&payload.content)
-        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    // Raw bodies go through the parse cache; session tokens reuse their scan
+    let cached = match (&payload.token, &payload.content) {
+        (None, Some(content)) => state.cache.get(content, &options),
+        _ => None,
+    };
+    let mut result = match (cached, &payload.lines) {
+        (_, Some(_)) if payload.content.is_some() || payload.token.is_some() => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "Send either lines or the script content/token, not both".to_string(),
+            ).into());
+        }
+        (_, Some(lines)) => {
+            if lines.len() > MAX_LINES {
+                return Err((
+                    StatusCode::PAYLOAD_TOO_LARGE,
+                    format!("Input exceeds the maximum of {} lines", MAX_LINES),
+                ).into());
+            }
+            calculate_line_durations(lines, &options).map_err(ApiError::parse)?
+        }
+        (Some(result), None) => result,
+        (None, None) => {
+            let script = resolve_script(
+                &state.sessions,
+                payload.content.as_deref(),
+                payload.token.as_deref(),
+                &options,
+            )?;
+            let result = script
+                .durations(&options)
+                .map_err(ApiError::parse)?;
+            if payload.token.is_none() {
+                state.cache.insert(script.input(), &options, &result);
+            }
+            result
+        }
+    };
     
+    if result.line_count() == 0 && !payload.allow_empty {
+        return Err(ApiError::no_timestamps());
+    }
+
+    if let Some(offset) = payload.offset_secs {
+        result.offset_spans(offset);
+    }
+    if payload.endpoints {
+        result.include_endpoints();
+    }
+    if let Some(step) = payload.round_total_to_secs {
+        result
+            .round_total(step, payload.round_total_mode)
+            .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    }
+    if payload.stats || payload.percentiles.is_some() {
+        let percentiles = payload.percentiles.as_deref().unwrap_or_default();
+        result.compute_stats(percentiles).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    }
+    let format_locale = FormatLocale {
+        grouping_separator: payload.grouping_separator.or(locale.grouping_separator),
+        pad_minutes: payload.pad_minutes,
+        decimal_separator: payload.decimal_separator.or(locale.decimal_comma.then_some(',')),
+    };
+    if payload.iso8601 {
+        result.include_iso8601();
+    }
+    if payload.decimal_minutes {
+        result.include_minutes(&format_locale);
+    }
+    if let Some(target) = payload.target_secs {
+        result.set_target(target);
+    }
+    if let Some(target) = payload.target_per_line_secs {
+        result.set_line_target(target);
+    }
+    if format_locale.grouping_separator.is_some() || format_locale.pad_minutes {
+        result.localize(&format_locale);
+    }
+    if payload.bars || payload.bar_width.is_some() {
+        let width = payload.bar_width.unwrap_or(DEFAULT_BAR_WIDTH);
+        result.include_bars(width).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    }
+    if payload.histogram || payload.bucket_bounds_secs.is_some() {
+        let bounds = payload.bucket_bounds_secs.as_deref().unwrap_or(DEFAULT_BUCKET_BOUNDS);
+        result.bucket_lines(bounds).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    }
+    if let Some(label) = &payload.total_label {
+        result.label_totals(label);
+    }
+
+    if let Some(sort) = payload.sort {
+        result.sort_lines(sort);
+    }
+    if page.offset > 0 || page.limit.is_some() {
+        result.paginate(page.offset, page.limit);
+    }
+    if payload.compact {
+        return Ok(Json(result.compact()).into_response());
+    }
+
+    Ok(Json(result).into_response())
+}
+
+async fn clean(
+    State(state): State<SharedState>,
+    locale: Locale,
+    Json(payload): Json<CleanRequest>,
+) -> Result<Json<CleanResponse>, ApiError> {
+    payload
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    let options = payload.options.parse_options(&state, &locale);
+    let script = resolve_script(
+        &state.sessions,
+        payload.script.as_deref(),
+        payload.token.as_deref(),
+        &options,
+    )?;
+
+    if let Some(max_removals) = payload.preview {
+        if payload.edits || payload.malformed_only || payload.removed_spans {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "preview cannot be combined with edits, malformed_only or removed_spans".to_string(),
+            ).into());
+        }
+        let preview = script.clean_preview(max_removals);
+        return Ok(Json(CleanResponse {
+            changed: preview.cleaned != script.input(),
+            cleaned: Some(preview.cleaned),
+            edits: None,
+            original: script.input().to_string(),
+            remaining: Some(preview.remaining),
+            removed_spans: None,
+        }));
+    }
+
+    let removed_spans = payload.removed_spans.then(|| {
+        if payload.malformed_only {
+            script.clean_malformed_spans()
+        } else {
+            script.clean_spans()
+        }
+    });
+
+    if payload.edits {
+        let edits = if payload.malformed_only {
+            script.clean_malformed_edits()
+        } else {
+            script.clean_edits()
+        };
+        return Ok(Json(CleanResponse {
+            cleaned: None,
+            changed: !edits.is_empty(),
+            edits: Some(edits),
+            original: script.input().to_string(),
+            remaining: None,
+            removed_spans,
+        }));
+    }
+
+    let cleaned = if payload.malformed_only {
+        script.clean_malformed()
+    } else {
+        script.clean()
+    };
+    Ok(Json(CleanResponse {
+        changed: cleaned != script.input(),
+        cleaned: Some(cleaned),
+        edits: None,
+        original: script.input().to_string(),
+        remaining: None,
+        removed_spans,
+    }))
+}
+
+#[derive(Deserialize)]
+struct CleanBatchItem {
+    id: serde_json::Value,
+    script: String,
+}
+
+// One item's outcome; `id` is null when the item could not be read at all
+#[derive(Serialize)]
+struct CleanBatchResult {
+    id: serde_json::Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cleaned: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+fn clean_batch_item(value: serde_json::Value, options: &ParseOptions) -> CleanBatchResult {
+    let failed = |id, error| CleanBatchResult { id, cleaned: None, error: Some(error) };
+    let fallback_id = value.get("id").cloned().unwrap_or_default();
+    let item = match serde_json::from_value::<CleanBatchItem>(value) {
+        Ok(item) => item,
+        Err(e) => return failed(fallback_id, format!("Invalid item: {}", e)),
+    };
+
+    if item.script.len() > MAX_BATCH_ITEM_BYTES {
+        let error = format!("Script exceeds the maximum of {} bytes", MAX_BATCH_ITEM_BYTES);
+        return failed(item.id, error);
+    }
+    if item.script.lines().count() > MAX_LINES {
+        return failed(item.id, format!("Script exceeds the maximum of {} lines", MAX_LINES));
+    }
+    CleanBatchResult {
+        id: item.id,
+        cleaned: Some(clean_script(&item.script, options)),
+        error: None,
+    }
+}
+
+// Cleans many `{"id": .., "script": ..}` items, sent as a JSON array or as
+// JSON Lines, with the options `/clean` takes given in the query string.
+// Results keep the input order; a bad item only fails itself.
+async fn clean_batch(
+    State(state): State<SharedState>,
+    locale: Locale,
+    Query(options): Query<RequestOptions>,
+    body: String,
+) -> Result<Json<Vec<CleanBatchResult>>, ApiError> {
+    let items: Vec<Result<serde_json::Value, String>> = if body.trim_start().starts_with('[') {
+        serde_json::from_str::<Vec<serde_json::Value>>(&body)
+            .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid batch: {}", e)))?
+            .into_iter()
+            .map(Ok)
+            .collect()
+    } else {
+        body.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line).map_err(|e| format!("Invalid JSON line: {}", e)))
+            .collect()
+    };
+
+    if items.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "The batch is empty".to_string()).into());
+    }
+    if items.len() > MAX_BATCH_ITEMS {
+        return Err((
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("Batch exceeds the maximum of {} items", MAX_BATCH_ITEMS),
+        ).into());
+    }
+
+    let options = options.parse_options(&state, &locale);
+    let results = items
+        .into_iter()
+        .map(|item| match item {
+            Ok(item) => clean_batch_item(item, &options),
+            Err(error) => CleanBatchResult {
+                id: serde_json::Value::Null,
+                cleaned: None,
+                error: Some(error),
+            },
+        })
+        .collect();
+    Ok(Json(results))
+}
+
+// Chunks of cleaned text buffered between the cleaner and the response body
+const CLEAN_STREAM_BUFFER: usize = 16;
+
+async fn clean_stream(
+    State(state): State<SharedState>,
+    locale: Locale,
+    Json(payload): Json<CleanRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    payload
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    let options = payload.options.parse_options(&state, &locale);
+    let script = resolve_script(
+        &state.sessions,
+        payload.script.as_deref(),
+        payload.token.as_deref(),
+        &options,
+    )?;
+
+    // Clean on a blocking thread and forward each chunk as it is produced
+    let malformed_only = payload.malformed_only;
+    let (tx, rx) = mpsc::channel::<Result<String, Infallible>>(CLEAN_STREAM_BUFFER);
+    tokio::task::spawn_blocking(move || {
+        let segments = if malformed_only {
+            script.clean_malformed_segments()
+        } else {
+            script.clean_segments()
+        };
+        for segment in segments {
+            if tx.blocking_send(Ok(segment)).is_err() {
+                break; // Client went away
+            }
+        }
+    });
+
+    Ok((
+        [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+        Body::from_stream(ReceiverStream::new(rx)),
+    ))
+}
+
+async fn normalize(
+    State(state): State<SharedState>,
+    locale: Locale,
+    Json(payload): Json<NormalizeRequest>,
+) -> Result<Json<NormalizeResponse>, ApiError> {
+    payload
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    let options = payload.options.parse_options(&state, &locale);
+    let script = resolve_script(
+        &state.sessions,
+        payload.content.as_deref(),
+        payload.token.as_deref(),
+        &options,
+    )?;
+
+    let normalized = script.normalize().map_err(ApiError::parse)?;
+    Ok(Json(NormalizeResponse {
+        changed: normalized != script.input(),
+        normalized,
+    }))
+}
+
+async fn stats(
+    State(state): State<SharedState>,
+    locale: Locale,
+    Json(payload): Json<StatsRequest>,
+) -> Result<Json<DurationStats>, ApiError> {
+    payload
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    let options = payload.options.parse_options(&state, &locale);
+    let script = resolve_script(
+        &state.sessions,
+        payload.content.as_deref(),
+        payload.token.as_deref(),
+        &options,
+    )?;
+
+    let mut result = script
+        .durations(&options)
+        .map_err(ApiError::parse)?;
+    result
+        .compute_stats(&payload.percentiles)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    result
+        .stats
+        .map(Json)
+        .ok_or_else(ApiError::no_timestamps)
+}
+
+async fn session(
+    State(state): State<SharedState>,
+    locale: Locale,
+    Json(payload): Json<SessionRequest>,
+) -> Result<Json<SessionResponse>, ApiError> {
+    payload
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    check_line_limit(&payload.content)?;
+
+    let script = ParsedScript::parse(&payload.content, &payload.options.parse_options(&state, &locale));
+    let ranges = script.range_count();
+    let token = state.sessions.insert(script);
+    Ok(Json(SessionResponse {
+        token,
+        expires_in_secs: SESSION_TTL.as_secs(),
+        ranges,
+    }))
+}
+
+// Total one side of a comparison, naming it in errors
+fn compare_side(name: &str, content: &str, options: &ParseOptions) -> Result<CompareSide, ApiError> {
+    check_line_limit(content)?;
+    let result = calculate_durations(content, options)
+        .map_err(|e| ApiError::parse(ScriptError { message: format!("Script {}: {}", name, e), ..e }))?;
+    Ok(CompareSide {
+        lines: result.line_count(),
+        total: result.total,
+    })
+}
+
+async fn compare(
+    State(state): State<SharedState>,
+    locale: Locale,
+    Json(payload): Json<CompareRequest>,
+) -> Result<Json<CompareResponse>, ApiError> {
+    payload
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    let options = payload.options.parse_options(&state, &locale);
+
+    let a = compare_side("a", &payload.a, &options)?;
+    let b = compare_side("b", &payload.b, &options)?;
+    Ok(Json(CompareResponse {
+        delta: DurationDelta::between(a.total.seconds, b.total.seconds),
+        line_delta: b.lines as i64 - a.lines as i64,
+        a,
+        b,
+    }))
+}
+
+async fn split(
+    State(state): State<SharedState>,
+    locale: Locale,
+    Query(query): Query<SplitQuery>,
+    Json(payload): Json<SplitRequest>,
+) -> Result<Json<SplitResponse>, ApiError> {
+    payload
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    if query.n == 0 || query.n > MAX_SPLIT_PARTS {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("n must be between 1 and {}", MAX_SPLIT_PARTS),
+        ).into());
+    }
+    check_line_limit(&payload.content)?;
+
+    let result = calculate_durations(&payload.content, &payload.options.parse_options(&state, &locale))
+        .map_err(ApiError::parse)?;
+    if result.line_count() == 0 {
+        return Err(ApiError::no_timestamps());
+    }
+
+    Ok(Json(SplitResponse {
+        splits: split_points(result.total.seconds, query.n),
+        total: result.total,
+        parts: query.n,
+    }))
+}
+
+async fn longest(
+    State(state): State<SharedState>,
+    locale: Locale,
+    Json(payload): Json<ScriptRequest>,
+) -> Result<Json<WithWarnings<CoveredSpan>>, ApiError> {
+    payload
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    check_line_limit(&payload.content)?;
+
+    let (span, skipped) = longest_span(&payload.content, &payload.options.parse_options(&state, &locale))
+        .map_err(ApiError::parse)?;
+    let span = span.ok_or_else(ApiError::no_timestamps)?;
+    Ok(Json(WithWarnings::new(span, skipped)))
+}
+
+async fn order(
+    State(state): State<SharedState>,
+    locale: Locale,
+    Json(payload): Json<ScriptRequest>,
+) -> Result<Json<OrderCheck>, ApiError> {
+    payload
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    check_line_limit(&payload.content)?;
+
+    let check = check_order(&payload.content, &payload.options.parse_options(&state, &locale))
+        .map_err(ApiError::parse)?;
+    if check.ranges == 0 {
+        return Err(ApiError::no_timestamps());
+    }
+    Ok(Json(check))
+}
+
+async fn srt(Json(payload): Json<SrtRequest>) -> Result<Json<ParseOutput>, ApiError> {
+    payload
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    check_line_limit(&payload.content)?;
+
+    let result = parse_srt(&payload.content, payload.strict)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    if result.lines.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "No subtitle cues found".to_string()).into());
+    }
+
+    Ok(Json(result))
+}
+
+async fn audacity(Json(payload): Json<AudacityRequest>) -> Result<Json<ParseOutput>, ApiError> {
+    payload
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    check_line_limit(&payload.content)?;
+
+    let result = parse_audacity(&payload.content).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    if result.lines.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "No labels found".to_string()).into());
+    }
+
+    Ok(Json(result))
+}
+
+async fn transcript(Json(payload): Json<TranscriptRequest>) -> Result<Json<ParseOutput>, ApiError> {
+    payload
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    check_line_limit(&payload.content)?;
+
+    let result = parse_transcript(&payload.content, payload.end_secs)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
     if result.lines.is_empty() {
-        return Err((StatusCode::BAD_REQUEST, "No valid timestamps found".to_string()));
+        return Err((StatusCode::BAD_REQUEST, "No timestamped lines found".to_string()).into());
     }
 
     Ok(Json(result))
 }
 
-async fn clean(Json(payload): Json<CleanRequest>) -> Result<Json<CleanResponse>, (StatusCode, String)> {
+// Just the grand total, for summaries that do not need the lines
+async fn total(
+    State(state): State<SharedState>,
+    locale: Locale,
+    Json(payload): Json<TotalRequest>,
+) -> Result<Json<WithWarnings<DurationResult>>, ApiError> {
+    payload
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    check_line_limit(&payload.content)?;
+
+    let options = payload.options.parse_options(&state, &locale);
+    let (total, lines, skipped) = calculate_total(&payload.content, &options)
+        .map_err(ApiError::parse)?;
+
+    if lines == 0 && !payload.allow_empty {
+        return Err(ApiError::no_timestamps());
+    }
+
+    Ok(Json(WithWarnings::new(total, skipped)))
+}
+
+async fn budget(
+    State(state): State<SharedState>,
+    locale: Locale,
+    Json(payload): Json<BudgetRequest>,
+) -> Result<Json<WithWarnings<Budget>>, ApiError> {
+    payload
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    check_line_limit(&payload.content)?;
+
+    let options = payload.options.parse_options(&state, &locale);
+    let (total, lines, skipped) = calculate_total(&payload.content, &options)
+        .map_err(ApiError::parse)?;
+
+    if lines == 0 {
+        return Err(ApiError::no_timestamps());
+    }
+
+    Ok(Json(WithWarnings::new(Budget::new(total, payload.target_secs), skipped)))
+}
+
+async fn explain(
+    State(state): State<SharedState>,
+    locale: Locale,
+    Json(payload): Json<ScriptRequest>,
+) -> Result<Json<Explanation>, ApiError> {
+    payload
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    check_line_limit(&payload.content)?;
+
+    let options = payload.options.parse_options(&state, &locale);
+    let explanation = explain_durations(&payload.content, &options)
+        .map_err(ApiError::parse)?;
+
+    if explanation.lines.is_empty() {
+        return Err(ApiError::no_timestamps());
+    }
+
+    Ok(Json(explanation))
+}
+
+async fn reading_time(
+    State(state): State<SharedState>,
+    locale: Locale,
+    Json(payload): Json<ReadingRequest>,
+) -> Result<Json<ReadingEstimate>, ApiError> {
     payload
         .validate()
         .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    check_line_limit(&payload.content)?;
 
-    let cleaned = clean_script(&payload.script);
-    Ok(Json(CleanResponse { cleaned }))
+    let options = payload.options.parse_options(&state, &locale);
+    let wpm = payload.wpm.unwrap_or(DEFAULT_WPM);
+    let estimate = estimate_reading(&payload.content, wpm, &options)
+        .map_err(ApiError::parse)?;
+    Ok(Json(estimate))
+}
+
+async fn label_groups(
+    State(state): State<SharedState>,
+    locale: Locale,
+    Json(payload): Json<LabelGroupsRequest>,
+) -> Result<(HeaderMap, Json<Vec<LabelGroup>>), ApiError> {
+    payload
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    check_line_limit(&payload.content)?;
+
+    let options = payload.options.parse_options(&state, &locale);
+    let (groups, skipped) = group_by_label(&payload.content, &options, &payload.delimiter, payload.depth.unwrap_or(1))
+        .map_err(ApiError::parse)?;
+
+    if groups.is_empty() {
+        return Err(ApiError::no_timestamps());
+    }
+
+    Ok((warning_headers(&skipped), Json(groups)))
+}
+
+async fn speakers(
+    State(state): State<SharedState>,
+    locale: Locale,
+    Json(payload): Json<ScriptRequest>,
+) -> Result<(HeaderMap, Json<BTreeMap<String, DurationResult>>), ApiError> {
+    payload
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    check_line_limit(&payload.content)?;
+
+    let options = payload.options.parse_options(&state, &locale);
+    let (totals, skipped) = speaker_totals(&payload.content, &options).map_err(ApiError::parse)?;
+
+    if totals.is_empty() {
+        return Err(ApiError::no_timestamps());
+    }
+
+    Ok((warning_headers(&skipped), Json(totals)))
+}
+
+async fn coverage(
+    State(state): State<SharedState>,
+    locale: Locale,
+    Json(payload): Json<ScriptRequest>,
+) -> Result<Json<Coverage>, ApiError> {
+    payload
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    check_line_limit(&payload.content)?;
+
+    let coverage = measure_coverage(&payload.content, &payload.options.parse_options(&state, &locale))
+        .map_err(ApiError::parse)?;
+    Ok(Json(coverage))
+}
+
+async fn ffmetadata(
+    State(state): State<SharedState>,
+    locale: Locale,
+    Json(payload): Json<ScriptRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    payload
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    check_line_limit(&payload.content)?;
+
+    let (metadata, skipped) = ffmetadata_chapters(&payload.content, &payload.options.parse_options(&state, &locale))
+        .map_err(ApiError::parse)?;
+
+    Ok((
+        warning_headers(&skipped),
+        [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+        metadata,
+    ))
+}
+
+async fn markers(
+    State(state): State<SharedState>,
+    Json(payload): Json<MarkersRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    payload
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    check_line_limit(&payload.content)?;
+
+    let frame_rate = payload
+        .frame_rate
+        .or(state.default_frame_rate.map(FrameRate::get))
+        .ok_or((StatusCode::BAD_REQUEST, "A frame_rate is required".to_string()))?;
+    let csv = markers_csv(&payload.content, frame_rate)
+        .map_err(ApiError::parse)?;
+
+    Ok(([(header::CONTENT_TYPE, "text/csv; charset=utf-8")], csv))
+}
+
+// Read a comma-separated env var; `None` when unset, `Some(vec!["*"])` for the wildcard
+fn env_list(name: &str) -> Option<Vec<String>> {
+    let value = std::env::var(name).ok()?;
+    Some(
+        value
+            .split(',')
+            .map(|item| item.trim().to_string())
+            .filter(|item| !item.is_empty())
+            .collect(),
+    )
+}
+
+fn is_wildcard(items: &[String]) -> bool {
+    items.iter().any(|item| item == "*")
+}
+
+// CORS is locked down unless configured: no cross-origin access, GET/POST and
+// Content-Type only. Set CORS_ALLOWED_ORIGINS, CORS_ALLOWED_METHODS and
+// CORS_ALLOWED_HEADERS to comma-separated lists, or "*" to allow any (local dev).
+fn cors_layer() -> Result<CorsLayer, Error> {
+    let origins = match env_list("CORS_ALLOWED_ORIGINS") {
+        Some(items) if is_wildcard(&items) => AllowOrigin::from(Any),
+        Some(items) => AllowOrigin::list(
+            items
+                .iter()
+                .map(|item| HeaderValue::from_str(item))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("Invalid CORS_ALLOWED_ORIGINS: {}", e))?,
+        ),
+        None => AllowOrigin::list(Vec::<HeaderValue>::new()),
+    };
+
+    let methods = match env_list("CORS_ALLOWED_METHODS") {
+        Some(items) if is_wildcard(&items) => AllowMethods::from(Any),
+        Some(items) => AllowMethods::list(
+            items
+                .iter()
+                .map(|item| Method::from_bytes(item.to_uppercase().as_bytes()))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("Invalid CORS_ALLOWED_METHODS: {}", e))?,
+        ),
+        None => AllowMethods::list([Method::GET, Method::POST]),
+    };
+
+    let headers = match env_list("CORS_ALLOWED_HEADERS") {
+        Some(items) if is_wildcard(&items) => AllowHeaders::from(Any),
+        Some(items) => AllowHeaders::list(
+            items
+                .iter()
+                .map(|item| HeaderName::from_bytes(item.as_bytes()))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("Invalid CORS_ALLOWED_HEADERS: {}", e))?,
+        ),
+        None => AllowHeaders::list([header::CONTENT_TYPE]),
+    };
+
+    // Let browser clients read the correlation id echoed on every response,
+    // and the warnings of endpoints whose body cannot hold them
+    Ok(CorsLayer::new()
+        .allow_origin(origins)
+        .allow_methods(methods)
+        .allow_headers(headers)
+        .expose_headers([
+            HeaderName::from_static("x-request-id"),
+            HeaderName::from_static(WARNING_HEADER),
+        ]))
+}
+
+// Cache counters, gated like /selftest
+async fn cache_stats(
+    State(state): State<SharedState>,
+    headers: HeaderMap,
+) -> Result<Json<CacheStats>, (StatusCode, String)> {
+    check_selftest_token(&state, &headers)?;
+    Ok(Json(state.cache.stats()))
+}
+
+// Error counts, gated like /selftest
+async fn error_stats(
+    State(state): State<SharedState>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    check_selftest_token(&state, &headers)?;
+    Ok(Json(state.errors.snapshot()))
+}
+
+// Count every 400 answer under the category its handler attached; rejections
+// from extractors (e.g. malformed JSON) carry none and count as `other`
+async fn record_errors(State(state): State<SharedState>, request: Request, next: Next) -> Response {
+    let response = next.run(request).await;
+    if response.status() == StatusCode::BAD_REQUEST {
+        let category = response.extensions().get::<ErrorCategory>().copied();
+        state.errors.record(category.unwrap_or(ErrorCategory::Other));
+    }
+    response
+}
+
+// Known inputs with their expected total and cleaned script, checked by /selftest
+const SELFTEST_CASES: &[(&str, u32, &str)] = &[
+    ("Intro (0:00-1:30) and outro (2:00-2:15).", 105, "Intro and outro."),
+    ("(1:00:00-1:02:30) + (0:10-0:20)", 160, ""),
+    ("Dash – style (0:00–0:45)", 45, "Dash – style"),
+    ("Ellipsis (1:00...1:30) here", 30, "Ellipsis here"),
+    ("Literal \\(0:00-1:00) stays (0:05-0:10)", 5, "Literal (0:00-1:00) stays"),
+];
+
+const SELFTEST_TOKEN_HEADER: &str = "x-selftest-token";
+
+#[derive(Serialize)]
+struct SelftestCase {
+    input: &'static str,
+    passed: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    failures: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct SelftestResponse {
+    passed: bool,
+    cases: Vec<SelftestCase>,
+}
+
+fn run_selftest_case(input: &'static str, total: u32, cleaned: &str) -> SelftestCase {
+    let options = ParseOptions::default();
+    let mut failures = Vec::new();
+    match calculate_durations(input, &options) {
+        Ok(result) if result.total.seconds == total => {}
+        Ok(result) => failures.push(format!("total {} instead of {}", result.total.seconds, total)),
+        Err(e) => failures.push(format!("parse failed: {}", e)),
+    }
+    let actual = clean_script(input, &options);
+    if actual != cleaned {
+        failures.push(format!("cleaned {:?} instead of {:?}", actual, cleaned));
+    }
+    SelftestCase {
+        input,
+        passed: failures.is_empty(),
+        failures,
+    }
+}
+
+// Compares secrets in time independent of where they differ, so response
+// timing does not reveal how much of a guess is right (only its length)
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+// Operator endpoints are hidden (404) unless X2_SELFTEST_TOKEN is set, and
+// require that token in the x-selftest-token header
+fn check_selftest_token(state: &AppState, headers: &HeaderMap) -> Result<(), (StatusCode, String)> {
+    let Some(expected) = state.selftest_token.as_deref() else {
+        return Err((StatusCode::NOT_FOUND, "Not found".to_string()));
+    };
+    let token = headers.get(SELFTEST_TOKEN_HEADER).map(|value| value.as_bytes());
+    if !token.is_some_and(|token| constant_time_eq(token, expected.as_bytes())) {
+        return Err((StatusCode::UNAUTHORIZED, "Invalid self-test token".to_string()));
+    }
+    Ok(())
+}
+
+// Post-deploy smoke check against built-in examples, gated by the self-test token
+async fn selftest(
+    State(state): State<SharedState>,
+    headers: HeaderMap,
+) -> Result<Response, (StatusCode, String)> {
+    check_selftest_token(&state, &headers)?;
+
+    let cases: Vec<SelftestCase> = SELFTEST_CASES
+        .iter()
+        .map(|&(input, total, cleaned)| run_selftest_case(input, total, cleaned))
+        .collect();
+    let passed = cases.iter().all(|case| case.passed);
+    let status = if passed { StatusCode::OK } else { StatusCode::INTERNAL_SERVER_ERROR };
+    Ok((status, Json(SelftestResponse { passed, cases })).into_response())
 }
 
 #[tokio::main]
@@ -71,20 +1770,268 @@ async fn main() -> Result<(), Error> {
     dotenv().ok();
 
     // Add CORS layer
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
+    let cors = cors_layer()?;
+
+    // Parse result cache, off unless X2_CACHE_CAPACITY is set
+    let cache = ParseCache::new(
+        env_number("X2_CACHE_CAPACITY", 0)?,
+        Duration::from_secs(env_number("X2_CACHE_TTL_SECS", 300)?),
+    );
+    // Server-wide default frame rate; request values take precedence
+    let default_frame_rate = match std::env::var("X2_FRAME_RATE") {
+        Ok(value) => {
+            let fps = value
+                .trim()
+                .parse()
+                .map_err(|_| format!("Invalid X2_FRAME_RATE: {}", value))?;
+            Some(FrameRate::new(fps).map_err(|e| format!("Invalid X2_FRAME_RATE: {}", e))?)
+        }
+        Err(_) => None,
+    };
+
+    let state = Arc::new(AppState {
+        sessions: SessionStore::new(env_number("X2_MAX_SESSIONS", DEFAULT_MAX_SESSIONS)?),
+        cache,
+        errors: ErrorStats::default(),
+        default_frame_rate,
+        selftest_token: std::env::var("X2_SELFTEST_TOKEN").ok().filter(|token| !token.is_empty()),
+    });
 
     let router = Router::new()
         .route("/", get(hello))
+        .route("/info", get(info))
         .route("/timestamp", post(timestamp))
+        .route("/total", post(total))
+        .route("/budget", post(budget))
         .route("/clean", post(clean))
+        .route("/clean/stream", post(clean_stream))
+        .route("/clean/batch", post(clean_batch))
+        .route("/session", post(session))
+        .route("/normalize", post(normalize))
+        .route("/stats", post(stats))
+        .route("/compare", post(compare))
+        .route("/split", post(split))
+        .route("/longest-span", post(longest))
+        .route("/order", post(order))
+        .route("/srt", post(srt))
+        .route("/transcript", post(transcript))
+        .route("/audacity", post(audacity))
+        .route("/explain", post(explain))
+        .route("/reading-time", post(reading_time))
+        .route("/coverage", post(coverage))
+        .route("/label-groups", post(label_groups))
+        .route("/speakers", post(speakers))
+        .route("/markers", post(markers))
+        .route("/ffmetadata", post(ffmetadata))
+        .route("/stats/cache", get(cache_stats))
+        .route("/stats/errors", get(error_stats))
+        .route("/selftest", get(selftest))
         .route("/favicon.ico", get(favicon))
-        .layer(cors);
+        .layer(middleware::from_fn_with_state(state.clone(), record_errors))
+        .layer(middleware::from_fn(require_utf8))
+        .layer(DefaultBodyLimit::max(MAX_BODY_BYTES))
+        .layer(cors)
+        // Echo the caller's X-Request-Id, or a fresh UUID when there is none,
+        // on every response, errors and CORS preflights included
+        .layer(PropagateRequestIdLayer::x_request_id())
+        .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+        .with_state(state);
 
     let app = ServiceBuilder::new()
         .layer(VercelLayer::new())
         .service(router);
     vercel_runtime::run(app).await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selftest_tokens_compare_whole_values() {
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secret-longer"));
+        assert!(!constant_time_eq(b"", b"secret"));
+    }
+
+    async fn utf8_checked(body: Vec<u8>) -> (StatusCode, String) {
+        use tower::ServiceExt;
+        let app = Router::new()
+            .route("/", post(|body: String| async move { body }))
+            .layer(middleware::from_fn(require_utf8));
+        let request = Request::post("/").body(Body::from(body)).unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, String::from_utf8(bytes.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn invalid_utf8_bodies_are_rejected_with_the_offset() {
+        let (status, body) = utf8_checked(b"(0:00-1:00) ok".to_vec()).await;
+        assert_eq!((status, body.as_str()), (StatusCode::OK, "(0:00-1:00) ok"));
+
+        let (status, body) = utf8_checked(b"abc\xff(0:00-1:00)".to_vec()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body, "Request body must be valid UTF-8 (invalid byte at offset 3)");
+
+        // A truncated multi-byte sequence and a lone continuation byte
+        let (status, body) = utf8_checked(b"ab\xe2\x82".to_vec()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body.ends_with("(invalid byte at offset 2)"), "{body}");
+        let (status, _) = utf8_checked(b"\x80".to_vec()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    fn test_state(selftest_token: Option<&str>) -> SharedState {
+        Arc::new(AppState {
+            sessions: SessionStore::new(DEFAULT_MAX_SESSIONS),
+            cache: ParseCache::new(0, Duration::ZERO),
+            errors: ErrorStats::default(),
+            default_frame_rate: None,
+            selftest_token: selftest_token.map(str::to_string),
+        })
+    }
+
+    #[tokio::test]
+    async fn errors_are_counted_under_the_category_their_handler_attached() {
+        use tower::ServiceExt;
+        let state = test_state(Some("secret"));
+        let app = Router::new()
+            .route("/total", post(total))
+            .route("/stats/errors", get(error_stats))
+            .layer(middleware::from_fn_with_state(state.clone(), record_errors))
+            .with_state(state.clone());
+        let post_json = |body: &'static str| {
+            Request::post("/total")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        for body in [r#"{"content": "(0:20-0:10)"}"#, r#"{"content": "no ranges"}"#, "{not json"] {
+            let response = app.clone().oneshot(post_json(body)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        }
+        let snapshot = state.errors.snapshot();
+        assert_eq!(snapshot["total"], 3);
+        assert_eq!(snapshot["categories"]["end_before_start"], 1);
+        assert_eq!(snapshot["categories"]["no_timestamps"], 1);
+        assert_eq!(snapshot["categories"]["other"], 1);
+
+        let stats = |token: Option<&'static str>| {
+            let mut request = Request::get("/stats/errors");
+            if let Some(token) = token {
+                request = request.header(SELFTEST_TOKEN_HEADER, token);
+            }
+            app.clone().oneshot(request.body(Body::empty()).unwrap())
+        };
+        assert_eq!(stats(None).await.unwrap().status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(stats(Some("wrong")).await.unwrap().status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(stats(Some("secret")).await.unwrap().status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn stats_are_hidden_without_a_token() {
+        let response = error_stats(State(test_state(None)), HeaderMap::new()).await;
+        assert_eq!(response.unwrap_err().0, StatusCode::NOT_FOUND);
+        let response = cache_stats(State(test_state(None)), HeaderMap::new()).await;
+        assert_eq!(response.unwrap_err().0, StatusCode::NOT_FOUND);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(SELFTEST_TOKEN_HEADER, HeaderValue::from_static("wrong"));
+        let response = cache_stats(State(test_state(Some("secret"))), headers.clone()).await;
+        assert_eq!(response.unwrap_err().0, StatusCode::UNAUTHORIZED);
+        headers.insert(SELFTEST_TOKEN_HEADER, HeaderValue::from_static("secret"));
+        assert!(cache_stats(State(test_state(Some("secret"))), headers).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn skipped_spans_are_returned_as_warnings() {
+        use tower::ServiceExt;
+        let app = Router::new()
+            .route("/total", post(total))
+            .route("/labels", post(label_groups))
+            .with_state(test_state(None));
+        let body = r#"{"content": "(0:00-1::00) Act 1 - a\n(0:00-0:10) Act 1 - b", "delimiter": " - ", "skip_malformed": true}"#;
+        let post_json = |uri: &str| {
+            Request::post(uri)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(post_json("/total")).await.unwrap();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(json["seconds"], 10);
+        assert_eq!(json["warnings"][0], "Malformed timestamp: (0:00-1::00)");
+
+        // A list body has no room for them, so they come as headers
+        let response = app.oneshot(post_json("/labels")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let warnings: Vec<_> = response.headers().get_all(WARNING_HEADER).iter().collect();
+        assert_eq!(warnings, ["Malformed timestamp: (0:00-1::00)"]);
+    }
+
+    #[tokio::test]
+    async fn batch_items_fail_alone_and_take_the_query_options() {
+        use tower::ServiceExt;
+        let app = Router::new()
+            .route("/clean/batch", post(clean_batch))
+            .with_state(test_state(None));
+        let body = r#"[{"id": 1, "script": "A (0:00-1:00) and (1:00-2:00) B"}, {"script": 5}, {"id": 3, "script": "C (0:00-0:10)"}]"#;
+        let request = Request::post("/clean/batch?word_connectors=true").body(Body::from(body)).unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let results: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(results[0]["cleaned"], "A B");
+        assert!(results[1]["error"].as_str().unwrap().starts_with("Invalid item"));
+        assert_eq!(results[2]["cleaned"], "C");
+    }
+
+    /// Collects the field names a struct's `Deserialize` impl asks for.
+    struct FieldNames<'a>(&'a mut &'static [&'static str]);
+
+    impl<'de> serde::Deserializer<'de> for FieldNames<'_> {
+        type Error = serde::de::value::Error;
+
+        fn deserialize_any<V: serde::de::Visitor<'de>>(self, _: V) -> Result<V::Value, Self::Error> {
+            Err(serde::de::Error::custom("only structs are inspected"))
+        }
+
+        fn deserialize_struct<V: serde::de::Visitor<'de>>(
+            self,
+            _: &'static str,
+            fields: &'static [&'static str],
+            _: V,
+        ) -> Result<V::Value, Self::Error> {
+            *self.0 = fields;
+            Err(serde::de::Error::custom("fields collected"))
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+            bytes byte_buf option unit unit_struct newtype_struct seq tuple
+            tuple_struct map enum identifier ignored_any
+        }
+    }
+
+    #[tokio::test]
+    async fn info_lists_every_request_option() {
+        let mut fields: &'static [&'static str] = &[];
+        let _ = RequestOptions::deserialize(FieldNames(&mut fields));
+        assert!(!fields.is_empty());
+
+        let response = info(State(test_state(None))).await.into_response();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let info: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        let options = info["options"].as_array().unwrap();
+        for field in fields {
+            assert!(options.iter().any(|option| option == field), "/info is missing {field}");
+        }
+    }
+}
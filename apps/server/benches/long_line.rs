@@ -0,0 +1,59 @@
+// Timing for a degenerate script: one line holding 10k ranges, plus 10k
+// bracketed asides that are not ranges. Run with `cargo bench --bench long_line`;
+// doubling the counts should roughly double every time.
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use x2_colon_api::parser::{ParseOptions, calculate_durations, clean_edits, clean_script};
+
+const RUNS: u32 = 20;
+
+fn chained_line(count: usize) -> String {
+    let mut line = String::new();
+    for i in 0..count {
+        line.push_str(&format!("(0:{:02}-0:{:02}) + ", i % 50, i % 50 + 5));
+    }
+    line.push_str("(0:00-0:01)");
+    line
+}
+
+fn prose_line(count: usize) -> String {
+    let mut line = String::new();
+    for i in 0..count {
+        line.push_str(&format!("word (aside {}: see note) (0:00-0:01) ", i));
+    }
+    line
+}
+
+fn time(label: &str, mut f: impl FnMut()) {
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        f();
+    }
+    let average: Duration = start.elapsed() / RUNS;
+    println!("{:<32} {:?}", label, average);
+}
+
+fn main() {
+    let options = ParseOptions::default();
+    for count in [5_000, 10_000, 20_000] {
+        let chained = chained_line(count);
+        let prose = prose_line(count);
+        println!("{} ranges on one line", count);
+        time("  calculate_durations (chained)", || {
+            black_box(calculate_durations(&chained, &options).unwrap());
+        });
+        time("  clean_script (chained)", || {
+            black_box(clean_script(&chained, &options));
+        });
+        time("  calculate_durations (prose)", || {
+            black_box(calculate_durations(&prose, &options).unwrap());
+        });
+        time("  clean_script (prose)", || {
+            black_box(clean_script(&prose, &options));
+        });
+        time("  clean_edits (prose)", || {
+            black_box(clean_edits(&prose, &options));
+        });
+    }
+}
@@ -0,0 +1,54 @@
+// Timing of the `parallel` feature on a 50k-range script, on a single rayon
+// thread versus the default pool. Run with
+// `cargo bench --bench parallel --features parallel`.
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use x2_colon_api::parser::{ParseOptions, calculate_durations};
+
+const RUNS: u32 = 10;
+const RANGES: usize = 50_000;
+
+fn script(count: usize) -> String {
+    let mut script = String::new();
+    for i in 0..count {
+        let start = i % 3000;
+        script.push_str(&format!(
+            "Shot {} (0:{:02}:{:02}-0:{:02}:{:02})\n",
+            i,
+            start / 60,
+            start % 60,
+            (start + 5) / 60,
+            (start + 5) % 60
+        ));
+    }
+    script
+}
+
+fn time(label: &str, threads: usize, mut f: impl FnMut() + Send) -> Duration {
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+    let average = pool.install(|| {
+        let start = Instant::now();
+        for _ in 0..RUNS {
+            f();
+        }
+        start.elapsed() / RUNS
+    });
+    println!("{:<32} {:?}", label, average);
+    average
+}
+
+fn main() {
+    let options = ParseOptions::default();
+    let script = script(RANGES);
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+
+    println!("{} ranges", RANGES);
+    let single = time("  1 thread", 1, || {
+        black_box(calculate_durations(&script, &options).unwrap());
+    });
+    let pooled = time(&format!("  {} threads", threads), threads, || {
+        black_box(calculate_durations(&script, &options).unwrap());
+    });
+    println!("  speedup {:.2}x", single.as_secs_f64() / pooled.as_secs_f64());
+}
@@ -0,0 +1,109 @@
+use nom::{
+    IResult, Parser,
+    character::complete::{digit0, digit1, one_of},
+    combinator::{all_consuming, opt, recognize},
+};
+
+use crate::parser::{DurationResult, LineResult, ParseOutput};
+
+// Decimal seconds as written by Audacity, "12.345678" (some versions write a
+// decimal comma), converted to whole milliseconds
+fn decimal_seconds(input: &str) -> IResult<&str, u64> {
+    let (rest, (whole, fraction)) = (digit1, opt(recognize((one_of(".,"), digit0)))).parse(input)?;
+    let fraction = fraction.map_or("", |fraction| &fraction[1..]);
+    let mut millis = whole.parse::<u64>().unwrap_or(u64::MAX / 1000).saturating_mul(1000);
+    // First three digits are the millis; the fourth rounds them
+    let digits: Vec<u64> = fraction.bytes().take(4).map(|b| (b - b'0') as u64).collect();
+    for (place, digit) in digits.iter().take(3).enumerate() {
+        millis += digit * 10u64.pow(2 - place as u32);
+    }
+    if digits.get(3).is_some_and(|&digit| digit >= 5) {
+        millis += 1;
+    }
+    Ok((rest, millis))
+}
+
+fn parse_time(field: &str) -> Option<u64> {
+    all_consuming(decimal_seconds).parse(field.trim()).ok().map(|(_, millis)| millis)
+}
+
+fn rounded_seconds(millis: u64) -> u32 {
+    ((millis + 500) / 1000).min(u32::MAX as u64) as u32
+}
+
+/// Parses an Audacity label track export: one `start<TAB>end<TAB>label` row
+/// per label, times in decimal seconds. Each row becomes a line identified by
+/// its row number, with the label as its `input`.
+///
+/// Point labels (start equal to end) last zero seconds. The spectral rows
+/// Audacity writes after a label (starting with `\`) and blank rows are
+/// skipped. Durations are rounded to whole seconds per label, while the total
+/// is rounded from the exact sum.
+pub fn parse_audacity(input: &str) -> Result<ParseOutput, String> {
+    let normalized = input.trim_start_matches('\u{feff}');
+    let mut lines = Vec::new();
+    let mut total_millis = 0u64;
+
+    for (index, row) in normalized.lines().enumerate() {
+        let row_number = index + 1;
+        if row.trim().is_empty() || row.starts_with('\\') {
+            continue;
+        }
+
+        let mut fields = row.trim_end_matches('\r').splitn(3, '\t');
+        let (Some(start), Some(end)) = (fields.next(), fields.next()) else {
+            return Err(format!(
+                "Row {}: expected start, end and label separated by tabs: {}",
+                row_number, row
+            ));
+        };
+        let label = fields.next().unwrap_or("").trim();
+
+        let start = parse_time(start)
+            .ok_or_else(|| format!("Row {}: malformed start time: {}", row_number, start.trim()))?;
+        let end = parse_time(end)
+            .ok_or_else(|| format!("Row {}: malformed end time: {}", row_number, end.trim()))?;
+        if end < start {
+            return Err(format!(
+                "Row {}: end time {} is before start time {}",
+                row_number,
+                end as f64 / 1000.0,
+                start as f64 / 1000.0
+            ));
+        }
+
+        let duration = end - start;
+        total_millis += duration;
+        lines.push(LineResult {
+            id: row_number,
+            input: label.to_string(),
+            result: DurationResult::new(rounded_seconds(duration)),
+            warnings: Vec::new(),
+            expression: None,
+            span: Some((rounded_seconds(start), rounded_seconds(end))),
+            start: None,
+            end: None,
+            order: None,
+            target_delta: None,
+            bar: None,
+        });
+    }
+
+    let mut output = ParseOutput {
+        lines,
+        total: DurationResult::new(rounded_seconds(total_millis)),
+        filtered: None,
+        sections: None,
+        remaining: None,
+        rounding_warning: None,
+        totals: None,
+        total_lines: None,
+        adjustments: None,
+        histogram: None,
+        stats: None,
+        warnings: None,
+        rounded_total: None,
+    };
+    output.note_rounding();
+    Ok(output)
+}
@@ -0,0 +1,266 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::parser::{ParseOptions, ParseOutput};
+
+struct Entry {
+    input: String,
+    options: ParseOptions,
+    output: ParseOutput,
+    inserted: Instant,
+    last_used: u64,
+}
+
+// Entries by key hash, plus recency order (use tick -> key) for LRU eviction
+#[derive(Default)]
+struct LruState {
+    entries: HashMap<u64, Entry>,
+    recency: BTreeMap<u64, u64>,
+    tick: u64,
+}
+
+impl LruState {
+    fn touch(&mut self, key: u64) {
+        self.tick += 1;
+        if let Some(entry) = self.entries.get_mut(&key) {
+            self.recency.remove(&entry.last_used);
+            entry.last_used = self.tick;
+            self.recency.insert(self.tick, key);
+        }
+    }
+
+    fn remove(&mut self, key: u64) {
+        if let Some(entry) = self.entries.remove(&key) {
+            self.recency.remove(&entry.last_used);
+        }
+    }
+}
+
+/// Hit/miss counters of a `ParseCache`.
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheStats {
+    pub capacity: usize,
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    pub hit_rate: f64,
+}
+
+/// A thread-safe LRU cache of successful parse results, keyed by the input
+/// text and options. Entries expire after `ttl`; a capacity of 0 disables it.
+pub struct ParseCache {
+    capacity: usize,
+    ttl: Duration,
+    state: Mutex<LruState>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+fn cache_key(input: &str, options: &ParseOptions) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    input.hash(&mut hasher);
+    options.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl ParseCache {
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        ParseCache {
+            capacity,
+            ttl,
+            state: Mutex::new(LruState::default()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    /// Returns a copy of the cached output for this input and options, if fresh.
+    pub fn get(&self, input: &str, options: &ParseOptions) -> Option<ParseOutput> {
+        if !self.is_enabled() {
+            return None;
+        }
+
+        let key = cache_key(input, options);
+        let mut state = self.state.lock().unwrap();
+        let found = match state.entries.get(&key) {
+            // Compare the full key so a hash collision is only a miss
+            Some(entry) if entry.input == input && entry.options == *options => {
+                if entry.inserted.elapsed() < self.ttl {
+                    Some(entry.output.clone())
+                } else {
+                    state.remove(key);
+                    None
+                }
+            }
+            _ => None,
+        };
+
+        if found.is_some() {
+            state.touch(key);
+            self.hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+        }
+        found
+    }
+
+    /// Stores an output, evicting the least recently used entry when full.
+    pub fn insert(&self, input: &str, options: &ParseOptions, output: &ParseOutput) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let key = cache_key(input, options);
+        let mut state = self.state.lock().unwrap();
+        state.remove(key);
+        while state.entries.len() >= self.capacity {
+            let Some((_, oldest)) = state.recency.pop_first() else {
+                break;
+            };
+            state.entries.remove(&oldest);
+            self.evictions.fetch_add(1, Ordering::Relaxed);
+        }
+
+        state.entries.insert(
+            key,
+            Entry {
+                input: input.to_string(),
+                options: options.clone(),
+                output: output.clone(),
+                inserted: Instant::now(),
+                last_used: 0,
+            },
+        );
+        state.touch(key);
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let hits = self.hits.load(Ordering::Relaxed);
+        let misses = self.misses.load(Ordering::Relaxed);
+        let lookups = hits + misses;
+        CacheStats {
+            capacity: self.capacity,
+            entries: self.state.lock().unwrap().entries.len(),
+            hits,
+            misses,
+            evictions: self.evictions.load(Ordering::Relaxed),
+            hit_rate: if lookups == 0 { 0.0 } else { hits as f64 / lookups as f64 },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{calculate_durations, format_duration};
+
+    fn output(seconds: u32) -> ParseOutput {
+        let input = format!("(0:00-{})", format_duration(seconds));
+        calculate_durations(&input, &ParseOptions::default()).unwrap()
+    }
+
+    fn cached_total(cache: &ParseCache, input: &str) -> Option<u32> {
+        cache.get(input, &ParseOptions::default()).map(|output| output.total.seconds)
+    }
+
+    #[test]
+    fn least_recently_used_entries_are_evicted_first() {
+        let cache = ParseCache::new(2, Duration::from_secs(60));
+        let options = ParseOptions::default();
+        cache.insert("a", &options, &output(1));
+        cache.insert("b", &options, &output(2));
+        // Using "a" leaves "b" as the oldest
+        assert_eq!(cached_total(&cache, "a"), Some(1));
+        cache.insert("c", &options, &output(3));
+
+        assert_eq!(cached_total(&cache, "b"), None);
+        assert_eq!(cached_total(&cache, "a"), Some(1));
+        assert_eq!(cached_total(&cache, "c"), Some(3));
+        let stats = cache.stats();
+        assert_eq!((stats.entries, stats.evictions), (2, 1));
+    }
+
+    #[test]
+    fn entries_expire_after_the_ttl() {
+        let cache = ParseCache::new(4, Duration::from_millis(20));
+        cache.insert("a", &ParseOptions::default(), &output(1));
+        assert_eq!(cached_total(&cache, "a"), Some(1));
+        std::thread::sleep(Duration::from_millis(40));
+        assert_eq!(cached_total(&cache, "a"), None);
+        assert_eq!(cache.stats().entries, 0);
+    }
+
+    #[test]
+    fn a_hash_collision_is_a_miss() {
+        let cache = ParseCache::new(4, Duration::from_secs(60));
+        let options = ParseOptions::default();
+        cache.insert("a", &options, &output(1));
+        // Plant the entry under the key another input hashes to
+        {
+            let mut state = cache.state.lock().unwrap();
+            let entry = state.entries.remove(&cache_key("a", &options)).unwrap();
+            state.entries.insert(cache_key("b", &options), entry);
+        }
+        assert_eq!(cached_total(&cache, "b"), None);
+    }
+
+    #[test]
+    fn zero_capacity_disables_the_cache() {
+        let cache = ParseCache::new(0, Duration::from_secs(60));
+        assert!(!cache.is_enabled());
+        cache.insert("a", &ParseOptions::default(), &output(1));
+        assert_eq!(cached_total(&cache, "a"), None);
+        let stats = cache.stats();
+        assert_eq!((stats.entries, stats.hits, stats.misses), (0, 0, 0));
+    }
+
+    #[test]
+    fn hits_and_misses_are_counted() {
+        let cache = ParseCache::new(4, Duration::from_secs(60));
+        let options = ParseOptions::default();
+        assert_eq!(cached_total(&cache, "a"), None);
+        cache.insert("a", &options, &output(1));
+        assert_eq!(cached_total(&cache, "a"), Some(1));
+        assert_eq!(cached_total(&cache, "a"), Some(1));
+        // Other options are another key
+        assert!(cache.get("a", &ParseOptions::lenient()).is_none());
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses), (2, 2));
+        assert_eq!(stats.hit_rate, 0.5);
+    }
+
+    #[test]
+    fn concurrent_use_keeps_the_counters_consistent() {
+        let cache = ParseCache::new(8, Duration::from_secs(60));
+        std::thread::scope(|scope| {
+            for thread in 0..4 {
+                let cache = &cache;
+                scope.spawn(move || {
+                    for i in 0..50 {
+                        let input = format!("{}", (thread + i) % 12);
+                        if cache.get(&input, &ParseOptions::default()).is_none() {
+                            cache.insert(&input, &ParseOptions::default(), &output(1));
+                        }
+                    }
+                });
+            }
+        });
+        let stats = cache.stats();
+        assert_eq!(stats.hits + stats.misses, 200);
+        assert!(stats.entries <= 8);
+    }
+}
@@ -0,0 +1,99 @@
+use serde::Serialize;
+
+use crate::parser::{ParseOptions, ScriptError, clean_script, find_all_ranges};
+use crate::reading::word_count;
+
+/// How densely a script is marked up with timestamps.
+///
+/// - `range_chars`: non-whitespace characters inside ranges and bare duration
+///   operands, counted in Unicode scalar values.
+/// - `total_chars`: non-whitespace characters in the whole input.
+/// - `char_ratio`: `range_chars / total_chars`, rounded to four places; 0 for
+///   an input with no visible characters.
+/// - `ranges`: `(start-end)` ranges, bare durations excluded.
+/// - `words`: words of prose left once the ranges are removed, counted as by
+///   `estimate_reading`.
+/// - `ranges_per_1000_words`: `ranges * 1000 / words`, rounded to two places;
+///   `None` when there is no prose.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Coverage {
+    pub range_chars: usize,
+    pub total_chars: usize,
+    pub char_ratio: f64,
+    pub ranges: usize,
+    pub words: usize,
+    pub ranges_per_1000_words: Option<f64>,
+    // Malformed text passed over under `skip_malformed`, one message each
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warnings: Option<Vec<String>>,
+}
+
+fn visible_chars(text: &str) -> usize {
+    text.chars().filter(|c| !c.is_whitespace()).count()
+}
+
+fn round_to(value: f64, places: i32) -> f64 {
+    let scale = 10f64.powi(places);
+    (value * scale).round() / scale
+}
+
+/// Measures how much of a script its ranges take up; see `Coverage`.
+pub fn measure_coverage(input: &str, options: &ParseOptions) -> Result<Coverage, ScriptError> {
+    let (spans, skipped) = find_all_ranges(input, options)?;
+    let range_chars = spans
+        .iter()
+        .map(|range| visible_chars(&input[range.start_pos..range.end_pos]))
+        .sum();
+    let total_chars = visible_chars(input);
+    let ranges = spans.iter().filter(|range| !range.bare).count();
+    let words = word_count(&clean_script(input, options));
+
+    Ok(Coverage {
+        range_chars,
+        total_chars,
+        char_ratio: if total_chars == 0 { 0.0 } else { round_to(range_chars as f64 / total_chars as f64, 4) },
+        ranges,
+        words,
+        ranges_per_1000_words: (words > 0).then(|| round_to(ranges as f64 * 1000.0 / words as f64, 2)),
+        warnings: (!skipped.is_empty()).then_some(skipped),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coverage_counts_visible_chars_and_prose_words() {
+        let coverage = measure_coverage("Intro (0:00-1:00) hello  world\n", &ParseOptions::default()).unwrap();
+        assert_eq!(
+            coverage,
+            Coverage {
+                range_chars: 11,
+                total_chars: 26,
+                char_ratio: 0.4231,
+                ranges: 1,
+                words: 3,
+                ranges_per_1000_words: Some(333.33),
+                warnings: None,
+            }
+        );
+    }
+
+    #[test]
+    fn coverage_without_prose_or_text_has_no_rate() {
+        let coverage = measure_coverage("(0:00-1:00) (1:00-2:00)", &ParseOptions::default()).unwrap();
+        assert_eq!((coverage.char_ratio, coverage.ranges, coverage.words), (1.0, 2, 0));
+        assert_eq!(coverage.ranges_per_1000_words, None);
+
+        let coverage = measure_coverage(" \n ", &ParseOptions::default()).unwrap();
+        assert_eq!((coverage.total_chars, coverage.char_ratio), (0, 0.0));
+    }
+
+    #[test]
+    fn bare_durations_count_as_range_chars_but_not_ranges() {
+        let options = ParseOptions { bare_durations: true, ..ParseOptions::default() };
+        let coverage = measure_coverage("Intro (0:00-1:00) + 0:30 more", &options).unwrap();
+        assert_eq!((coverage.range_chars, coverage.ranges), (15, 1));
+    }
+}
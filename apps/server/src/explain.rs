@@ -0,0 +1,177 @@
+use serde::Serialize;
+
+use crate::parser::{
+    ParseOptions, ParsedRange, ScriptError, find_all_ranges, format_duration, joins_previous, range_error,
+};
+
+/// One step of the arithmetic behind a line's duration.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Step {
+    /// A `(start-end)` range: `elapsed` is `end - start`, and `seconds` the
+    /// duration counted, which differs from it under a speed factor or for a
+    /// comma-separated list of ranges in one bracket.
+    Range {
+        text: String,
+        start: u32,
+        end: u32,
+        elapsed: u32,
+        seconds: u32,
+        calculation: String,
+    },
+    /// A bare duration operand, counted as written.
+    Duration { text: String, seconds: u32 },
+    /// Operands joined with `+` on one line, added into a single duration.
+    Sum {
+        operands: Vec<u32>,
+        seconds: u32,
+        calculation: String,
+    },
+}
+
+/// How one line's duration was computed, step by step.
+#[derive(Debug, Clone, Serialize)]
+pub struct LineExplanation {
+    pub id: usize,
+    pub input: String,
+    pub steps: Vec<Step>,
+    pub seconds: u32,
+    pub format: String,
+}
+
+/// Every line's steps, plus the sum that produces the total.
+#[derive(Debug, Clone, Serialize)]
+pub struct Explanation {
+    pub lines: Vec<LineExplanation>,
+    pub total: u32,
+    pub format: String,
+    pub calculation: String,
+    // Malformed text passed over under `skip_malformed`, one message each
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warnings: Option<Vec<String>>,
+}
+
+// "60 + 30 = 90", or just "0" when nothing is added
+fn sum_calculation(operands: &[u32], sum: u32) -> String {
+    if operands.is_empty() {
+        return "0".to_string();
+    }
+    let terms: Vec<String> = operands.iter().map(u32::to_string).collect();
+    format!("{} = {}", terms.join(" + "), sum)
+}
+
+fn range_step(range: &ParsedRange) -> Step {
+    let Some(end) = range.end else {
+        return Step::Duration {
+            text: range.text.clone(),
+            seconds: range.duration,
+        };
+    };
+
+    let (start, end) = (range.start.to_seconds(), end.to_seconds());
+    let elapsed = end.saturating_sub(start);
+    let mut calculation = format!("{} - {} = {}", end, start, elapsed);
+    if range.duration != elapsed {
+        calculation.push_str(&format!(", counted as {}", range.duration));
+    }
+    Step::Range {
+        text: range.text.clone(),
+        start,
+        end,
+        elapsed,
+        seconds: range.duration,
+        calculation,
+    }
+}
+
+/// Breaks down how `calculate_durations` arrives at each line and the total,
+/// with start and end in seconds from zero.
+///
+/// Lines are grouped and numbered like `calculate_durations`, but every line is
+/// explained: `section_prefix` and `min_duration_secs` are not applied.
+pub fn explain_durations(input: &str, options: &ParseOptions) -> Result<Explanation, ScriptError> {
+    let (ranges, skipped) = find_all_ranges(input, options)?;
+    if let Some(error) = ranges.iter().find_map(range_error) {
+        return Err(error);
+    }
+
+    let mut lines: Vec<LineExplanation> = Vec::new();
+    for (i, range) in ranges.iter().enumerate() {
+        let step = range_step(range);
+        match lines.last_mut() {
+            Some(line) if i > 0 && joins_previous(input, &ranges[i - 1], range, options) => {
+                line.input = format!("{} + {}", line.input, range.text);
+                line.steps.push(step);
+                line.seconds = line.seconds.saturating_add(range.duration);
+            }
+            _ => lines.push(LineExplanation {
+                id: lines.len() + 1,
+                input: range.text.clone(),
+                steps: vec![step],
+                seconds: range.duration,
+                format: String::new(),
+            }),
+        }
+    }
+
+    for line in &mut lines {
+        if line.steps.len() > 1 {
+            let operands: Vec<u32> = line
+                .steps
+                .iter()
+                .filter_map(|step| match step {
+                    Step::Range { seconds, .. } | Step::Duration { seconds, .. } => Some(*seconds),
+                    Step::Sum { .. } => None,
+                })
+                .collect();
+            line.steps.push(Step::Sum {
+                calculation: sum_calculation(&operands, line.seconds),
+                operands,
+                seconds: line.seconds,
+            });
+        }
+        line.format = format_duration(line.seconds);
+    }
+
+    let operands: Vec<u32> = lines.iter().map(|line| line.seconds).collect();
+    let total = operands.iter().fold(0u32, |sum, &seconds| sum.saturating_add(seconds));
+    Ok(Explanation {
+        lines,
+        total,
+        format: format_duration(total),
+        calculation: sum_calculation(&operands, total),
+        warnings: (!skipped.is_empty()).then_some(skipped),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chained_ranges_are_explained_step_by_step() {
+        let explanation = explain_durations("(0:00-1:00) + (1:00-1:30)\n(2:00-2:10)", &ParseOptions::default()).unwrap();
+        assert_eq!(explanation.lines.len(), 2);
+        assert_eq!(explanation.lines[0].seconds, 90);
+        assert!(matches!(
+            explanation.lines[0].steps.last(),
+            Some(Step::Sum { seconds: 90, calculation, .. }) if calculation == "60 + 30 = 90"
+        ));
+        assert_eq!((explanation.total, explanation.calculation.as_str()), (100, "90 + 10 = 100"));
+    }
+
+    #[test]
+    fn huge_ranges_saturate() {
+        let big = "(0:00-1000000:00:00)";
+        let explanation = explain_durations(&format!("{big} + {big}"), &ParseOptions::default()).unwrap();
+        assert_eq!((explanation.lines[0].seconds, explanation.total), (u32::MAX, u32::MAX));
+
+        let explanation = explain_durations(&[big; 5].join("\n"), &ParseOptions::default()).unwrap();
+        assert_eq!(explanation.total, u32::MAX);
+    }
+
+    #[test]
+    fn invalid_ranges_fail() {
+        assert!(explain_durations("(2:00-1:00)", &ParseOptions::default()).is_err());
+    }
+}
@@ -0,0 +1,199 @@
+use crate::parser::{ErrorCategory, FrameRate, ParseOptions, ScriptError, find_all_ranges, line_label, range_error};
+
+/// Column header of the marker CSV produced by `markers_csv`.
+pub const MARKER_CSV_HEADER: &str = "Name,Timecode,Duration,Notes";
+
+// How timecode counts at a frame rate: whole rates as written, NTSC rates
+// (N/1.001, e.g. 29.97) at their exact rate with N frames per timecode second
+struct Timebase {
+    exact: f64,
+    nominal: u64,
+    // Drop-frame numbering keeps 29.97 and 59.94 timecode in step with the clock
+    drop_frame: bool,
+}
+
+impl Timebase {
+    // Reject frame rates that cannot describe real footage or timecode
+    fn new(frame_rate: f64) -> Result<Self, String> {
+        let frame_rate = FrameRate::new(frame_rate)?.get();
+        let whole = frame_rate.round();
+        if (frame_rate - whole).abs() < 1e-9 {
+            return Ok(Timebase { exact: whole, nominal: whole as u64, drop_frame: false });
+        }
+        let ntsc = (frame_rate * 1.001).round();
+        if ntsc >= 1.0 && (frame_rate * 1.001 - ntsc).abs() < 0.01 {
+            let nominal = ntsc as u64;
+            return Ok(Timebase {
+                exact: ntsc / 1.001,
+                nominal,
+                drop_frame: matches!(nominal, 30 | 60),
+            });
+        }
+        Err(format!(
+            "Invalid frame rate: {} (timecode needs a whole or NTSC rate, e.g. 25 or 29.97)",
+            frame_rate
+        ))
+    }
+
+    fn timecode(&self, seconds: u32) -> String {
+        let frames = (seconds as f64 * self.exact).round() as u64;
+        if self.drop_frame {
+            format_drop_frame_timecode(frames, self.nominal)
+        } else {
+            format_timecode(frames, self.nominal)
+        }
+    }
+}
+
+/// Renders a frame count as non-drop-frame SMPTE timecode (`HH:MM:SS:FF`).
+pub fn format_timecode(frames: u64, fps: u64) -> String {
+    let total_secs = frames / fps;
+    format!(
+        "{:02}:{:02}:{:02}:{:02}",
+        total_secs / 3600,
+        (total_secs / 60) % 60,
+        total_secs % 60,
+        frames % fps
+    )
+}
+
+/// Renders a frame count as drop-frame SMPTE timecode (`HH:MM:SS;FF`) for an
+/// NTSC rate counting `fps` (30 or 60) frames per timecode second: the first
+/// `fps / 15` frame numbers of every minute are skipped, except each tenth minute.
+pub fn format_drop_frame_timecode(frames: u64, fps: u64) -> String {
+    let dropped = fps / 15;
+    let per_minute = fps * 60 - dropped;
+    let per_ten_minutes = fps * 600 - dropped * 9;
+    let (tens, rest) = (frames / per_ten_minutes, frames % per_ten_minutes);
+    let mut numbered = frames + dropped * 9 * tens;
+    if rest > dropped {
+        numbered += dropped * ((rest - dropped) / per_minute);
+    }
+    let total_secs = numbered / fps;
+    format!(
+        "{:02}:{:02}:{:02};{:02}",
+        total_secs / 3600,
+        (total_secs / 60) % 60,
+        total_secs % 60,
+        numbered % fps
+    )
+}
+
+// Quote a CSV field when it contains a delimiter, quote or newline
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Converts every range in the input into a marker row for DaVinci Resolve,
+/// placed at the range start with the range length as its duration.
+///
+/// Frames are counted at the exact rate. NTSC rates such as 23.976 and 29.97
+/// are accepted; 29.97 and 59.94 use drop-frame timecode. Any other
+/// fractional rate is an error.
+pub fn markers_csv(input: &str, frame_rate: f64) -> Result<String, ScriptError> {
+    let timebase = Timebase::new(frame_rate).map_err(|message| ScriptError::new(ErrorCategory::Other, message))?;
+    // The default options never skip malformed spans, so nothing is dropped
+    let (ranges, _) = find_all_ranges(input, &ParseOptions::default())?;
+
+    if let Some(error) = ranges.iter().find_map(range_error) {
+        return Err(error);
+    }
+
+    let mut csv = String::from(MARKER_CSV_HEADER);
+    csv.push('\n');
+    // Bare duration operands have no position on the timeline
+    for (index, range) in ranges.iter().filter(|range| !range.bare).enumerate() {
+        let row = [
+            csv_field(&format!("Marker {}", index + 1)),
+            timebase.timecode(range.start.to_seconds()),
+            timebase.timecode(range.duration),
+            csv_field(&range.text),
+        ];
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+
+    Ok(csv)
+}
+
+/// First line of an FFmpeg metadata file, as produced by `ffmetadata_chapters`.
+pub const FFMETADATA_HEADER: &str = ";FFMETADATA1";
+
+// Backslash-escape the characters FFmpeg metadata gives a meaning to
+fn ffmetadata_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '=' | ';' | '#' | '\\' | '\n') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Converts every range into an FFmpeg `[CHAPTER]` block with a millisecond
+/// timebase. Each chapter is titled with the text of its line once the ranges
+/// are removed, or "Chapter N" when nothing is left. Also returns the messages
+/// of malformed spans passed over under `skip_malformed`.
+pub fn ffmetadata_chapters(input: &str, options: &ParseOptions) -> Result<(String, Vec<String>), ScriptError> {
+    let (ranges, skipped) = find_all_ranges(input, options)?;
+
+    if let Some(error) = ranges.iter().find_map(range_error) {
+        return Err(error);
+    }
+
+    let mut metadata = String::from(FFMETADATA_HEADER);
+    metadata.push('\n');
+    // Bare duration operands have no position on the timeline
+    for (index, range) in ranges.iter().filter(|range| !range.bare).enumerate() {
+        let Some(end) = range.end else { continue };
+        let title = line_label(input, range, options);
+        let title = if title.is_empty() { format!("Chapter {}", index + 1) } else { title };
+
+        metadata.push_str("\n[CHAPTER]\nTIMEBASE=1/1000\n");
+        metadata.push_str(&format!("START={}\n", range.start.to_seconds() as u64 * 1000));
+        metadata.push_str(&format!("END={}\n", end.to_seconds() as u64 * 1000));
+        metadata.push_str(&format!("title={}\n", ffmetadata_value(&title)));
+    }
+
+    Ok((metadata, skipped))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whole_rates_count_as_written() {
+        let csv = markers_csv("(1:00-1:30) intro", 25.0).unwrap();
+        assert_eq!(csv.lines().nth(1), Some("Marker 1,00:01:00:00,00:00:30:00,(1:00-1:30)"));
+    }
+
+    #[test]
+    fn ntsc_rates_count_frames_exactly() {
+        // 10 minutes at 29.97 is 17982 frames, which drop-frame labels 00:10:00;00
+        let csv = markers_csv("(10:00-10:01)", 29.97).unwrap();
+        assert_eq!(csv.lines().nth(1), Some("Marker 1,00:10:00;00,00:00:01;00,(10:00-10:01)"));
+
+        // 23.976 has no drop-frame form: 60 s is 1438.56 -> 1439 frames at 24 per timecode second
+        let csv = markers_csv("(1:00-1:01)", 23.976).unwrap();
+        assert_eq!(csv.lines().nth(1), Some("Marker 1,00:00:59:23,00:00:01:00,(1:00-1:01)"));
+    }
+
+    #[test]
+    fn other_fractional_rates_are_rejected() {
+        assert!(markers_csv("(0:00-0:01)", 25.5).unwrap_err().message.starts_with("Invalid frame rate: 25.5"));
+    }
+
+    #[test]
+    fn drop_frame_skips_frame_numbers_at_each_minute() {
+        assert_eq!(format_drop_frame_timecode(1799, 30), "00:00:59;29");
+        assert_eq!(format_drop_frame_timecode(1800, 30), "00:01:00;02");
+        assert_eq!(format_drop_frame_timecode(17982, 30), "00:10:00;00");
+        assert_eq!(format_drop_frame_timecode(3600, 60), "00:01:00;04");
+    }
+}
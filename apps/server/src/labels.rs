@@ -0,0 +1,137 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::parser::{
+    DurationResult, ErrorCategory, ParseOptions, ScriptError, find_all_ranges, joins_previous, line_label, range_error,
+};
+
+/// Label of the group collecting lines whose label has no prefix to group by.
+pub const UNGROUPED_LABEL: &str = "Ungrouped";
+
+/// Key of `speaker_totals` collecting lines with no `Name:` speaker tag.
+pub const UNATTRIBUTED_SPEAKER: &str = "unattributed";
+
+/// Lines sharing a label prefix, with their combined duration.
+#[derive(Debug, Clone, Serialize)]
+pub struct LabelGroup {
+    pub label: String,
+    pub lines: usize,
+    pub subtotal: DurationResult,
+}
+
+// The label up to its `depth`-th `delimiter`, or None when the label has no
+// more parts than that to group under
+fn label_prefix<'a>(label: &'a str, delimiter: &str, depth: usize) -> Option<&'a str> {
+    let (end, _) = label.match_indices(delimiter).nth(depth - 1)?;
+    let prefix = label[..end].trim();
+    (!prefix.is_empty()).then_some(prefix)
+}
+
+/// Subtotals per label prefix. A line's label is the text of its input line
+/// with the ranges removed; lines (`+` chains included) are grouped under the
+/// first `depth` parts of their label split at `delimiter`, so with `" - "` and
+/// depth 1, "Act 1 - Scene 2" counts towards "Act 1". Labels with no more than
+/// `depth` parts go to `UNGROUPED_LABEL`. Groups are listed in order of first
+/// appearance; every line counts, as `min_duration_secs` is not applied.
+/// Also returns the messages of malformed spans passed over under
+/// `skip_malformed`.
+pub fn group_by_label(
+    input: &str,
+    options: &ParseOptions,
+    delimiter: &str,
+    depth: usize,
+) -> Result<(Vec<LabelGroup>, Vec<String>), ScriptError> {
+    if delimiter.is_empty() {
+        return Err(ScriptError::new(ErrorCategory::Other, "The label delimiter must not be empty"));
+    }
+    if depth == 0 {
+        return Err(ScriptError::new(ErrorCategory::Other, "The label depth must be at least 1"));
+    }
+
+    let (ranges, skipped) = find_all_ranges(input, options)?;
+    if let Some(error) = ranges.iter().find_map(range_error) {
+        return Err(error);
+    }
+
+    let mut groups: Vec<(String, usize, u32)> = Vec::new();
+    let mut i = 0;
+    while i < ranges.len() {
+        let label = line_label(input, &ranges[i], options);
+        let mut seconds = ranges[i].duration;
+        while i + 1 < ranges.len() && joins_previous(input, &ranges[i], &ranges[i + 1], options) {
+            i += 1;
+            seconds = seconds.saturating_add(ranges[i].duration);
+        }
+        i += 1;
+
+        let key = label_prefix(&label, delimiter, depth).unwrap_or(UNGROUPED_LABEL);
+        match groups.iter_mut().find(|(label, _, _)| label == key) {
+            Some((_, lines, subtotal)) => {
+                *lines += 1;
+                *subtotal = subtotal.saturating_add(seconds);
+            }
+            None => groups.push((key.to_string(), 1, seconds)),
+        }
+    }
+
+    let groups = groups
+        .into_iter()
+        .map(|(label, lines, seconds)| LabelGroup {
+            label,
+            lines,
+            subtotal: DurationResult::new(seconds),
+        })
+        .collect();
+    Ok((groups, skipped))
+}
+
+// The `Name:` tag opening a line before its first range, as in
+// "Alice: (0:00-1:00)". The name starts with a letter and holds only letters,
+// digits, blanks and `.`, `'`, `-` or `_`, so times and prose are not taken
+fn speaker_tag(before_range: &str) -> Option<&str> {
+    let (name, _) = before_range.split_once(':')?;
+    let name = name.trim();
+    let valid = name.chars().next().is_some_and(char::is_alphabetic)
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == ' ' || matches!(c, '.' | '\'' | '-' | '_'));
+    valid.then_some(name)
+}
+
+/// Totals per speaker, taken from a `Name:` tag at the start of each line
+/// before its first range, e.g. "Alice: (0:00-1:00)". Lines (`+` chains
+/// included) without a tag count towards `UNATTRIBUTED_SPEAKER`. Every line
+/// counts, as `min_duration_secs` is not applied. Also returns the messages of
+/// malformed spans passed over under `skip_malformed`.
+pub fn speaker_totals(
+    input: &str,
+    options: &ParseOptions,
+) -> Result<(BTreeMap<String, DurationResult>, Vec<String>), ScriptError> {
+    let (ranges, skipped) = find_all_ranges(input, options)?;
+    if let Some(error) = ranges.iter().find_map(range_error) {
+        return Err(error);
+    }
+
+    let mut totals: BTreeMap<String, u32> = BTreeMap::new();
+    let mut i = 0;
+    while i < ranges.len() {
+        let line_start = input[..ranges[i].start_pos].rfind('\n').map_or(0, |pos| pos + 1);
+        let speaker = speaker_tag(&input[line_start..ranges[i].start_pos]).unwrap_or(UNATTRIBUTED_SPEAKER);
+        let mut seconds = ranges[i].duration;
+        while i + 1 < ranges.len() && joins_previous(input, &ranges[i], &ranges[i + 1], options) {
+            i += 1;
+            seconds = seconds.saturating_add(ranges[i].duration);
+        }
+        i += 1;
+
+        let total = totals.entry(speaker.to_string()).or_default();
+        *total = total.saturating_add(seconds);
+    }
+
+    let totals = totals
+        .into_iter()
+        .map(|(speaker, seconds)| (speaker, DurationResult::new(seconds)))
+        .collect();
+    Ok((totals, skipped))
+}
@@ -1 +1,10 @@
-pub mod parser;
\ No newline at end of file
+pub mod audacity;
+pub mod cache;
+pub mod coverage;
+pub mod explain;
+pub mod export;
+pub mod labels;
+pub mod parser;
+pub mod reading;
+pub mod srt;
+pub mod transcript;
\ No newline at end of file
@@ -1,43 +1,1018 @@
 use nom::{
     branch::alt,
-    bytes::complete::tag,
-    character::complete::digit1,
-    combinator::map_res,
-    sequence::{delimited, separated_pair},
+    bytes::complete::{tag, tag_no_case},
+    character::complete::{alpha1, digit1, multispace0, satisfy, space0, space1},
+    combinator::{all_consuming, map_res, not, opt, peek, recognize},
+    multi::{many0, separated_list1},
+    sequence::{delimited, preceded, separated_pair, terminated},
     IResult, Parser,
 };
 use regex::Regex;
-use serde::Serialize;
+use std::ops::Range;
+use std::sync::LazyLock;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy)]
-struct Timestamp {
-    hours: u32,
-    minutes: u32,
-    seconds: u32,
+/// Timestamp layouts accepted inside a range, in the order they are tried.
+pub const SUPPORTED_FORMATS: &[&str] = &["H:MM:SS", "M:SS"];
+
+/// Separators accepted between the start and end of a range.
+pub const RANGE_SEPARATORS: &[&str] = &["-", "–", "—", "..", "..."];
+
+/// Connectors that join consecutive ranges into a single line.
+pub const RANGE_CONNECTORS: &[&str] = &["+"];
+
+/// Words that also join ranges under `word_connectors`, written with blanks
+/// around them like `+`.
+pub const WORD_CONNECTORS: &[&str] = &["and", "&"];
+
+// `RANGE_CONNECTORS` followed by `WORD_CONNECTORS`
+const ALL_CONNECTORS: &[&str] = &["+", "and", "&"];
+
+// The connectors that join ranges under these options
+fn connectors(options: &ParseOptions) -> &'static [&'static str] {
+    if options.word_connectors { ALL_CONNECTORS } else { RANGE_CONNECTORS }
+}
+
+/// A point in time as written in a range, e.g. `1:02:03` or `4:05`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timestamp {
+    pub hours: u32,
+    pub minutes: u32,
+    pub seconds: u32,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct LineResult {
     pub id: usize,
     pub input: String,
     pub result: DurationResult,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    // Operand tree of a grouped line, when `expressions` is set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expression: Option<Expression>,
+    // Seconds from zero of the first range's start and the last range's end;
+    // `None` for lines made only of bare durations
+    #[serde(skip)]
+    pub span: Option<(u32, u32)>,
+    // `span` as durations from zero, filled by `ParseOutput::include_endpoints`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<DurationResult>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end: Option<DurationResult>,
+    // 0-based position before `ParseOutput::sort_lines` reordered the lines
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order: Option<usize>,
+    // Time to pad (positive) or trim (negative) to reach a per-line target
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_delta: Option<DurationDelta>,
+    // Proportional bar of block characters, filled by `ParseOutput::include_bars`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bar: Option<String>,
+}
+
+/// A node of a grouped line's expression, e.g. `(0:00-1:00) + 0:30`.
+/// Binary nodes associate to the left, in source order.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Expression {
+    /// A `(start-end)` range
+    Range { text: String, seconds: u32 },
+    /// A bare duration operand
+    Duration { text: String, seconds: u32 },
+    Binary {
+        operator: Operator,
+        left: Box<Expression>,
+        right: Box<Expression>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Operator {
+    #[serde(rename = "+")]
+    Add,
+}
+
+impl Expression {
+    fn operand(range: &ParsedRange) -> Self {
+        let text = range.text.clone();
+        let seconds = range.duration;
+        if range.bare {
+            Expression::Duration { text, seconds }
+        } else {
+            Expression::Range { text, seconds }
+        }
+    }
+
+    fn binary(operator: Operator, left: Expression, right: Expression) -> Self {
+        Expression::Binary {
+            operator,
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+    }
+
+    /// Evaluates the tree to a number of seconds.
+    pub fn seconds(&self) -> u32 {
+        match self {
+            Expression::Range { seconds, .. } | Expression::Duration { seconds, .. } => *seconds,
+            Expression::Binary { operator: Operator::Add, left, right } => left.seconds() + right.seconds(),
+        }
+    }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct DurationResult {
     pub seconds: u32,
     pub format: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iso8601: Option<String>,
+    // Decimal minutes to two places (90 seconds -> 1.5), for spreadsheets
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minutes: Option<f64>,
+    // `minutes` rendered with a locale's decimal separator, e.g. "1,5"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minutes_format: Option<String>,
+}
+
+impl DurationResult {
+    pub fn new(seconds: u32) -> Self {
+        DurationResult {
+            seconds,
+            format: format_duration(seconds),
+            iso8601: None,
+            minutes: None,
+            minutes_format: None,
+        }
+    }
+}
+
+/// Widths accepted by `ParseOutput::include_bars`, in characters.
+pub const BAR_WIDTH_RANGE: std::ops::RangeInclusive<usize> = 1..=200;
+
+/// Bar width used when a request asks for bars without giving one.
+pub const DEFAULT_BAR_WIDTH: usize = 20;
+
+/// Renders `seconds` as a bar of `width` characters, `█` for the filled part
+/// and `░` for the rest, scaled so that `max` fills it (e.g. `█████░░░░░` for
+/// half of `max`). Zero is all `░`, any other duration at least one `█`, and
+/// `max` (or more) a full bar; a `max` of zero leaves every bar empty.
+pub fn duration_bar(seconds: u32, max: u32, width: usize) -> String {
+    let filled = if seconds == 0 || max == 0 {
+        0
+    } else {
+        let scaled = (seconds.min(max) as u64 * width as u64 + max as u64 / 2) / max as u64;
+        (scaled as usize).max(1)
+    };
+    format!("{}{}", "█".repeat(filled), "░".repeat(width - filled))
+}
+
+/// Seconds as decimal minutes rounded to two places, e.g. 90 -> 1.5.
+pub fn decimal_minutes(seconds: u32) -> f64 {
+    (seconds as f64 / 60.0 * 100.0).round() / 100.0
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ParseOutput {
     pub lines: Vec<LineResult>,
     pub total: DurationResult,
+    // Headline total rounded to a step, `total` staying exact; see `round_total`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rounded_total: Option<DurationResult>,
+    // Number of lines dropped by `min_duration_secs`, when that option is set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filtered: Option<usize>,
+    // Per-section lines and subtotals when `section_prefix` is set; `lines` is then empty
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sections: Option<Vec<SectionResult>>,
+    // Time left before a target runtime, negative when over; see `set_target`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remaining: Option<DurationDelta>,
+    // Set when the displayed line durations do not add up to the displayed total.
+    // Only inputs with fractional times (SRT, Audacity labels) can trigger it:
+    // script ranges are rounded one by one and the total is their sum
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rounding_warning: Option<String>,
+    // Named subtotals and grand total for presentation; see `label_totals`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub totals: Option<Vec<LabeledTotal>>,
+    // Line count before `paginate` kept only a page of lines
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_lines: Option<usize>,
+    // Signed nudges found when `nudges` is set, never part of `total`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub adjustments: Option<Vec<Adjustment>>,
+    // Line counts per duration bucket; see `bucket_lines`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub histogram: Option<Vec<Bucket>>,
+    // Median and requested percentiles of the line durations; see `compute_stats`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stats: Option<DurationStats>,
+    // Malformed text passed over under `skip_malformed`, one message each
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warnings: Option<Vec<String>>,
+}
+
+/// Lines lasting from `min` seconds up to, but not including, `max`; the last
+/// bucket has no `max`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Bucket {
+    pub min: u32,
+    pub max: Option<u32>,
+    pub label: String,
+    pub count: usize,
+}
+
+/// Default bucket boundaries of `ParseOutput::bucket_lines`, in seconds:
+/// 0:00–0:30, 0:30–1:00, 1:00–2:00, 2:00–5:00 and 5:00 or more.
+pub const DEFAULT_BUCKET_BOUNDS: &[u32] = &[30, 60, 120, 300];
+
+/// Summary statistics of the line durations, from `ParseOutput::compute_stats`.
+/// Percentiles use the nearest-rank method, so every value is one of the
+/// line durations: see `nearest_rank`.
+#[derive(Debug, Clone, Serialize)]
+pub struct DurationStats {
+    pub count: usize,
+    pub min: DurationResult,
+    pub max: DurationResult,
+    /// The 50th percentile, i.e. the lower of the two middle lines when their
+    /// number is even
+    pub median: DurationResult,
+    pub percentiles: Vec<Percentile>,
+}
+
+/// The line duration at a requested percentile.
+#[derive(Debug, Clone, Serialize)]
+pub struct Percentile {
+    pub percentile: f64,
+    pub duration: DurationResult,
+}
+
+/// The nearest-rank percentile of ascending `sorted` values: the value at
+/// 1-based rank `ceil(p / 100 × n)`, so p90 of ten lines is the ninth and p100
+/// the longest. `p` must be above 0 and at most 100, and `sorted` non-empty.
+pub fn nearest_rank(sorted: &[u32], p: f64) -> u32 {
+    // Multiplying before dividing keeps whole percentiles exact, e.g. 95 × 20 / 100
+    let rank = (p * sorted.len() as f64 / 100.0).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// A signed nudge such as `(+0:05)` or `(-0:03)`, read with
+/// `ParseOptions::nudges`. Kept apart from the ranges and never counted.
+#[derive(Debug, Clone, Serialize)]
+pub struct Adjustment {
+    /// 1-based line of the input
+    pub line: usize,
+    pub text: String,
+    pub delta: DurationDelta,
+    #[serde(skip)]
+    pub(crate) span: Range<usize>,
+}
+
+/// Whether a `LabeledTotal` is a section's subtotal or the grand total.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TotalKind {
+    Subtotal,
+    Grand,
+}
+
+/// A duration with a display name, as listed in `ParseOutput::totals`.
+#[derive(Debug, Clone, Serialize)]
+pub struct LabeledTotal {
+    pub label: String,
+    pub kind: TotalKind,
+    pub duration: DurationResult,
+}
+
+/// Builds a `ParseOutput` from durations known ahead, such as timing data from
+/// another tool, with every `format` and the total derived from the seconds.
+/// Lines are numbered from 1 in the order they are added.
+#[derive(Debug, Clone, Default)]
+pub struct ParseOutputBuilder {
+    lines: Vec<LineResult>,
+}
+
+impl ParseOutputBuilder {
+    /// Adds a line labelled `input` lasting `seconds`.
+    pub fn line(self, input: impl Into<String>, seconds: u32) -> Self {
+        self.push(input.into(), seconds, None)
+    }
+
+    /// Adds a line labelled `input` from `start` to `end` seconds from zero,
+    /// so it can be shown with `ParseOutput::include_endpoints`. It lasts
+    /// `end - start`; an `end` before `start` counts as zero.
+    pub fn span(self, input: impl Into<String>, start: u32, end: u32) -> Self {
+        self.push(input.into(), end.saturating_sub(start), Some((start, end.max(start))))
+    }
+
+    fn push(mut self, input: String, seconds: u32, span: Option<(u32, u32)>) -> Self {
+        self.lines.push(LineResult {
+            id: self.lines.len() + 1,
+            input,
+            result: DurationResult::new(seconds),
+            warnings: Vec::new(),
+            expression: None,
+            span,
+            start: None,
+            end: None,
+            order: None,
+            target_delta: None,
+            bar: None,
+        });
+        self
+    }
+
+    /// The output, totalling the lines (capped at `u32::MAX` seconds).
+    pub fn build(self) -> ParseOutput {
+        let total = self.lines.iter().fold(0u32, |sum, line| sum.saturating_add(line.result.seconds));
+        ParseOutput {
+            lines: self.lines,
+            total: DurationResult::new(total),
+            rounded_total: None,
+            filtered: None,
+            sections: None,
+            remaining: None,
+            rounding_warning: None,
+            totals: None,
+            total_lines: None,
+            adjustments: None,
+            histogram: None,
+            stats: None,
+            warnings: None,
+        }
+    }
+}
+
+/// Label of lines before the first section header in `ParseOutput::totals`.
+pub const UNSECTIONED_LABEL: &str = "Unsectioned";
+
+impl ParseOutput {
+    /// Starts building an output from known durations rather than text.
+    pub fn builder() -> ParseOutputBuilder {
+        ParseOutputBuilder::default()
+    }
+
+    /// Calls `f` on every duration in the output: lines (with their endpoints),
+    /// section subtotals, labeled totals, stats, `rounded_total` and total.
+    pub fn for_each_duration(&mut self, mut f: impl FnMut(&mut DurationResult)) {
+        fn visit_line(line: &mut LineResult, f: &mut impl FnMut(&mut DurationResult)) {
+            f(&mut line.result);
+            line.start.iter_mut().chain(line.end.iter_mut()).for_each(f);
+        }
+        for line in &mut self.lines {
+            visit_line(line, &mut f);
+        }
+        for section in self.sections.iter_mut().flatten() {
+            for line in &mut section.lines {
+                visit_line(line, &mut f);
+            }
+            f(&mut section.subtotal);
+        }
+        for total in self.totals.iter_mut().flatten() {
+            f(&mut total.duration);
+        }
+        if let Some(stats) = &mut self.stats {
+            f(&mut stats.min);
+            f(&mut stats.max);
+            f(&mut stats.median);
+            stats.percentiles.iter_mut().for_each(|percentile| f(&mut percentile.duration));
+        }
+        self.rounded_total.iter_mut().for_each(&mut f);
+        f(&mut self.total);
+    }
+
+    /// Lists each section's subtotal under its header text, followed by the
+    /// grand total under `label` (e.g. "Episode runtime"), as `totals`.
+    pub fn label_totals(&mut self, label: &str) {
+        let subtotals = self.sections.iter().flatten().map(|section| LabeledTotal {
+            label: section.label.clone().unwrap_or_else(|| UNSECTIONED_LABEL.to_string()),
+            kind: TotalKind::Subtotal,
+            duration: section.subtotal.clone(),
+        });
+        let grand = LabeledTotal {
+            label: label.to_string(),
+            kind: TotalKind::Grand,
+            duration: self.total.clone(),
+        };
+        self.totals = Some(subtotals.chain(std::iter::once(grand)).collect());
+    }
+
+    /// Moves every line's position on the timeline `offset_secs` later, e.g. to
+    /// a clip's place in a master timeline. Durations are unchanged.
+    pub fn offset_spans(&mut self, offset_secs: u32) {
+        let sections = self.sections.iter_mut().flatten().flat_map(|section| &mut section.lines);
+        for line in self.lines.iter_mut().chain(sections) {
+            line.span = line
+                .span
+                .map(|(start, end)| (start.saturating_add(offset_secs), end.saturating_add(offset_secs)));
+        }
+    }
+
+    /// Fills the `start` and `end` of every line that has a position on the
+    /// timeline. Call before the other `include_*` methods so they apply too.
+    pub fn include_endpoints(&mut self) {
+        let sections = self.sections.iter_mut().flatten().flat_map(|section| &mut section.lines);
+        for line in self.lines.iter_mut().chain(sections) {
+            if let Some((start, end)) = line.span {
+                line.start = Some(DurationResult::new(start));
+                line.end = Some(DurationResult::new(end));
+            }
+        }
+    }
+
+    /// Re-renders every `format` string with the given locale, signed ones
+    /// (`remaining`, `target_delta`) included, so call it after setting targets.
+    pub fn localize(&mut self, locale: &FormatLocale) {
+        self.for_each_duration(|duration| {
+            duration.format = format_duration_localized(duration.seconds, locale);
+        });
+        let sections = self.sections.iter_mut().flatten().flat_map(|section| &mut section.lines);
+        let deltas = self.lines.iter_mut().chain(sections).filter_map(|line| line.target_delta.as_mut());
+        for delta in deltas.chain(self.remaining.as_mut()) {
+            delta.format = format_signed_duration_localized(delta.seconds, locale);
+        }
+    }
+
+    /// Fills the `iso8601` field of every duration.
+    pub fn include_iso8601(&mut self) {
+        self.for_each_duration(|duration| {
+            duration.iso8601 = Some(format_duration_iso8601(duration.seconds));
+        });
+    }
+
+    /// Fills the `minutes` field of every duration, and `minutes_format` too
+    /// when `locale` has a decimal separator.
+    pub fn include_minutes(&mut self, locale: &FormatLocale) {
+        self.for_each_duration(|duration| {
+            duration.minutes = Some(decimal_minutes(duration.seconds));
+            if locale.decimal_separator.is_some() {
+                duration.minutes_format = Some(format_decimal_minutes(duration.seconds, locale));
+            }
+        });
+    }
+
+    /// Reports the time left until `target_secs` (negative when over) as `remaining`.
+    pub fn set_target(&mut self, target_secs: u32) {
+        self.remaining = Some(DurationDelta::between(self.total.seconds, target_secs));
+    }
+
+    /// Sets each line's `target_delta` to the time it must gain (positive) or
+    /// lose (negative) to last `target_secs`.
+    pub fn set_line_target(&mut self, target_secs: u32) {
+        let sections = self.sections.iter_mut().flatten().flat_map(|section| &mut section.lines);
+        for line in self.lines.iter_mut().chain(sections) {
+            line.target_delta = Some(DurationDelta::between(line.result.seconds, target_secs));
+        }
+    }
+
+    /// Sets `rounded_total` to the total rounded to a multiple of `step_secs`
+    /// (60 for whole minutes), as a headline figure, saturating at `u32::MAX`.
+    /// `total` and the lines are left exact.
+    pub fn round_total(&mut self, step_secs: u32, mode: RoundMode) -> Result<(), String> {
+        if step_secs == 0 {
+            return Err("Invalid rounding step: 0 (must be above zero)".to_string());
+        }
+
+        let (total, step) = (self.total.seconds as u64, step_secs as u64);
+        let rounded = match mode {
+            RoundMode::Up => total.div_ceil(step) * step,
+            RoundMode::Nearest => (total + step / 2) / step * step,
+        };
+        self.rounded_total = Some(DurationResult::new(rounded.min(u32::MAX as u64) as u32));
+        Ok(())
+    }
+
+    /// Sets each line's `bar` to its duration as a `duration_bar` of `width`
+    /// characters, scaled to the longest line (in every section).
+    pub fn include_bars(&mut self, width: usize) -> Result<(), String> {
+        if !BAR_WIDTH_RANGE.contains(&width) {
+            return Err(format!(
+                "Invalid bar width: {} (must be between {} and {})",
+                width,
+                BAR_WIDTH_RANGE.start(),
+                BAR_WIDTH_RANGE.end()
+            ));
+        }
+
+        let sections = self.sections.iter().flatten().flat_map(|section| &section.lines);
+        let max = self.lines.iter().chain(sections).map(|line| line.result.seconds).max().unwrap_or(0);
+        let sections = self.sections.iter_mut().flatten().flat_map(|section| &mut section.lines);
+        for line in self.lines.iter_mut().chain(sections) {
+            line.bar = Some(duration_bar(line.result.seconds, max, width));
+        }
+        Ok(())
+    }
+
+    /// Counts lines (in every section) per duration bucket into `histogram`.
+    /// `bounds` are the boundaries between buckets, in seconds, and must be
+    /// strictly increasing and above zero; the first bucket starts at zero.
+    pub fn bucket_lines(&mut self, bounds: &[u32]) -> Result<(), String> {
+        if bounds.first() == Some(&0) || bounds.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(format!(
+                "Invalid bucket bounds: {:?} (must be increasing and above zero)",
+                bounds
+            ));
+        }
+
+        let mins = std::iter::once(0).chain(bounds.iter().copied());
+        let maxes = bounds.iter().copied().map(Some).chain(std::iter::once(None));
+        let mut histogram: Vec<Bucket> = mins
+            .zip(maxes)
+            .map(|(min, max)| Bucket {
+                min,
+                max,
+                label: match max {
+                    Some(max) => format!("{}–{}", format_duration(min), format_duration(max)),
+                    None => format!("{}+", format_duration(min)),
+                },
+                count: 0,
+            })
+            .collect();
+        let sections = self.sections.iter().flatten().flat_map(|section| &section.lines);
+        for line in self.lines.iter().chain(sections) {
+            let index = bounds.partition_point(|&bound| bound <= line.result.seconds);
+            histogram[index].count += 1;
+        }
+        self.histogram = Some(histogram);
+        Ok(())
+    }
+
+    /// Fills `stats` with the count, extremes, median and each of `percentiles`
+    /// of the line durations (in every section), by nearest rank. Percentiles
+    /// must be above 0 and at most 100; `stats` stays empty without lines.
+    pub fn compute_stats(&mut self, percentiles: &[f64]) -> Result<(), String> {
+        if let Some(p) = percentiles.iter().find(|&&p| !(p > 0.0 && p <= 100.0)) {
+            return Err(format!("Invalid percentile: {} (must be above 0 and at most 100)", p));
+        }
+
+        let sections = self.sections.iter().flatten().flat_map(|section| &section.lines);
+        let mut sorted: Vec<u32> = self.lines.iter().chain(sections).map(|line| line.result.seconds).collect();
+        if sorted.is_empty() {
+            return Ok(());
+        }
+        sorted.sort_unstable();
+
+        self.stats = Some(DurationStats {
+            count: sorted.len(),
+            min: DurationResult::new(sorted[0]),
+            max: DurationResult::new(sorted[sorted.len() - 1]),
+            median: DurationResult::new(nearest_rank(&sorted, 50.0)),
+            percentiles: percentiles
+                .iter()
+                .map(|&percentile| Percentile {
+                    percentile,
+                    duration: DurationResult::new(nearest_rank(&sorted, percentile)),
+                })
+                .collect(),
+        });
+        Ok(())
+    }
+
+    /// Flags a total that differs from the sum of its lines, which happens when
+    /// lines are rounded one by one but the total is rounded from the exact sum,
+    /// as for SRT cues and Audacity labels. Script totals are sums of whole
+    /// seconds (speed factors and frame ranges round each range first), so they
+    /// never set it. Clients can then mark the total as approximate.
+    pub fn note_rounding(&mut self) {
+        let sections = self.sections.iter().flatten().flat_map(|section| &section.lines);
+        let sum: u64 = self.lines.iter().chain(sections).map(|line| line.result.seconds as u64).sum();
+        self.rounding_warning = (sum != self.total.seconds as u64).then(|| {
+            format!(
+                "Lines add up to {} but the exact total rounds to {}",
+                format_duration(sum.min(u32::MAX as u64) as u32),
+                self.total.format
+            )
+        });
+    }
+
+    /// Sorts lines by duration (within each section), recording each line's
+    /// original position in `order`. Ties keep their original order.
+    pub fn sort_lines(&mut self, sort: SortOrder) {
+        let sections = self.sections.iter_mut().flatten().map(|section| &mut section.lines);
+        for lines in std::iter::once(&mut self.lines).chain(sections) {
+            for (position, line) in lines.iter_mut().enumerate() {
+                line.order = Some(position);
+            }
+            match sort {
+                SortOrder::Asc => lines.sort_by_key(|line| line.result.seconds),
+                SortOrder::Desc => lines.sort_by_key(|line| std::cmp::Reverse(line.result.seconds)),
+            }
+        }
+    }
+
+    /// Converts to the positional `CompactOutput` shape.
+    pub fn compact(&self) -> CompactOutput {
+        let sections = self.sections.iter().flatten().flat_map(|section| &section.lines);
+        let lines = self
+            .lines
+            .iter()
+            .chain(sections)
+            .map(|line| {
+                let (start, end) = line.span.unzip();
+                (line.id, start, end, line.result.seconds)
+            })
+            .collect();
+        CompactOutput {
+            lines,
+            total: self.total.seconds,
+        }
+    }
+
+    /// Keeps `limit` lines (all when `None`) starting at the 0-based `offset`,
+    /// counting across sections in order; sections left without lines are
+    /// dropped. Totals and subtotals still cover every line, and `total_lines`
+    /// records the full count.
+    pub fn paginate(&mut self, offset: usize, limit: Option<usize>) {
+        let total_lines = self.line_count();
+        let end = limit.map_or(total_lines, |limit| offset.saturating_add(limit));
+        let mut position = 0;
+        let mut in_page = |_: &LineResult| {
+            position += 1;
+            (offset..end).contains(&(position - 1))
+        };
+        self.lines.retain(&mut in_page);
+        if let Some(sections) = &mut self.sections {
+            for section in sections.iter_mut() {
+                section.lines.retain(&mut in_page);
+            }
+            sections.retain(|section| !section.lines.is_empty());
+        }
+        self.total_lines = Some(total_lines);
+    }
+
+    /// Number of lines, counting those nested in sections.
+    pub fn line_count(&self) -> usize {
+        match &self.sections {
+            Some(sections) => sections.iter().map(|section| section.lines.len()).sum(),
+            None => self.lines.len(),
+        }
+    }
+}
+
+/// Combines outputs of pieces of a document parsed separately, in order: lines
+/// are concatenated and renumbered 1..n, totals (and `filtered` counts) are
+/// summed and adjustments and warnings concatenated. If any output has sections, the result
+/// is sectioned, with the lines of unsectioned outputs in an unlabeled section.
+/// Values derived for presentation (`remaining`, `totals`, pagination, sort
+/// order) are dropped.
+pub fn merge_outputs(outputs: &[ParseOutput]) -> ParseOutput {
+    let sectioned = outputs.iter().any(|output| output.sections.is_some());
+    let mut lines = Vec::new();
+    let mut sections = Vec::new();
+    let mut total = 0u32;
+    let mut filtered = None;
+    let mut adjustments: Option<Vec<Adjustment>> = None;
+    let mut warnings: Option<Vec<String>> = None;
+    for output in outputs {
+        if let Some(output_adjustments) = &output.adjustments {
+            adjustments.get_or_insert_default().extend(output_adjustments.iter().cloned());
+        }
+        if let Some(output_warnings) = &output.warnings {
+            warnings.get_or_insert_default().extend(output_warnings.iter().cloned());
+        }
+        total = total.saturating_add(output.total.seconds);
+        if let Some(count) = output.filtered {
+            filtered = Some(filtered.unwrap_or(0) + count);
+        }
+        match &output.sections {
+            Some(output_sections) => sections.extend(output_sections.iter().cloned()),
+            None if !sectioned => lines.extend(output.lines.iter().cloned()),
+            None if !output.lines.is_empty() => sections.push(SectionResult {
+                label: None,
+                lines: output.lines.clone(),
+                subtotal: DurationResult::new(output.total.seconds),
+            }),
+            None => {}
+        }
+    }
+
+    let section_lines = sections.iter_mut().flat_map(|section| &mut section.lines);
+    for (index, line) in lines.iter_mut().chain(section_lines).enumerate() {
+        line.id = index + 1;
+        line.order = None;
+    }
+
+    let mut merged = ParseOutput {
+        lines,
+        total: DurationResult::new(total),
+        filtered,
+        sections: sectioned.then_some(sections),
+        remaining: None,
+        rounding_warning: None,
+        totals: None,
+        total_lines: None,
+        adjustments,
+        histogram: None,
+        stats: None,
+        warnings,
+        rounded_total: None,
+    };
+    merged.note_rounding();
+    merged
+}
+
+/// Positional form of `ParseOutput` for bandwidth-sensitive clients.
+///
+/// Each line is `[id, start, end, seconds]`: `start` and `end` are seconds from
+/// zero of the first range's start and the last range's end (`null` when the
+/// line is only bare durations), `seconds` the line's duration. `total` is in
+/// seconds. Section lines are flattened, in order.
+#[derive(Debug, Clone, Serialize)]
+pub struct CompactOutput {
+    pub lines: Vec<(usize, Option<u32>, Option<u32>, u32)>,
+    pub total: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SectionResult {
+    // Header text without the prefix; None for lines before the first header
+    pub label: Option<String>,
+    pub lines: Vec<LineResult>,
+    pub subtotal: DurationResult,
+}
+
+/// Options for `calculate_durations`. The default reproduces the plain behavior.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ParseOptions {
+    /// Drop lines shorter than this many seconds from `lines` and `total`.
+    /// Applied to whole `" + "` groups, so a short component never splits a chain.
+    /// Dropped lines still use up their ids, so the ids kept may skip.
+    pub min_duration_secs: Option<u32>,
+    /// Accept bare durations as `+` operands after a range, e.g. `(0:00-1:00) + 0:30`.
+    /// A parenthesized range always takes precedence; a bare duration is only
+    /// recognized directly after a `+` that follows a range or another operand.
+    pub bare_durations: bool,
+    /// Flag any single range longer than this many seconds, checked per range
+    /// before grouping. Offending lines are kept; see `max_duration_severity`.
+    pub max_duration_secs: Option<u32>,
+    pub max_duration_severity: Severity,
+    /// Caps on the minutes and seconds of range endpoints, 59 by default.
+    pub bounds: BoundsPolicy,
+    /// Carry seconds over 59 into the minutes (`1:75` is 2:15) instead of
+    /// failing, with a warning on the line saying how the time was read.
+    pub overflow_seconds: bool,
+    /// Count `M:SS` times with minutes over 59 (`75:00` is 1:15:00) instead of
+    /// failing, with a warning on the line, since they are often typos.
+    /// Endpoints may mix layouts: `(1:30:00-95:00)` lasts 5:00.
+    pub warn_large_minutes: bool,
+    /// Read two-field times as `H:MM` instead of `M:SS`, so `(1:30-2:00)` is
+    /// half an hour, not half a minute. This changes the meaning of every
+    /// two-field time in the input, endpoints, bare durations and nudges alike,
+    /// and nothing in the text tells the two readings apart: only set it for
+    /// inputs known to be written in hours and minutes. Three-field `H:MM:SS`
+    /// times are read as usual.
+    pub hh_mm: bool,
+    /// Flag ranges that start and end at the same time, like `(1:00-1:00)`,
+    /// which are usually typos. `None` keeps them as zero-length lines.
+    pub zero_length: Option<Severity>,
+    /// Read full-width and other common Unicode digits, and the full-width colon
+    /// `：`, as their ASCII forms. Reported positions and text stay in the original.
+    pub unicode_digits: bool,
+    /// Split the script into sections at lines starting with this prefix (e.g. `#`),
+    /// returning per-section lines and subtotals. `None` keeps the flat output.
+    pub section_prefix: Option<String>,
+    /// Accept a speed modifier after a range, e.g. `(0:00-4:00)@0.5x`: the range
+    /// lasts duration / factor, rounded to whole seconds (so 0.5x doubles it).
+    pub speed_factors: bool,
+    /// Stop with an error as soon as the running total passes this many
+    /// seconds, naming the line that pushed it over. Applied after
+    /// `min_duration_secs`, so dropped lines do not count.
+    pub max_total_secs: Option<u32>,
+    /// Accept a repeat count after a range, with or without a blank before it:
+    /// `(0:00-0:30)x4` and `(0:00-0:30) x4` both last 2:00. Cleaning removes
+    /// the count along with the range. Applied after any speed factor.
+    pub multipliers: bool,
+    /// Also accept a decimal comma in speed factors, e.g. `@0,5x`.
+    pub decimal_comma: bool,
+    /// Accept frames-only ranges such as `(0f-240f)`, converted to seconds at
+    /// `frame_rate`; such a range without a frame rate is an error.
+    pub frame_ranges: bool,
+    pub frame_rate: Option<FrameRate>,
+    /// Allow blanks inside timestamps, as in PDF-extracted text: around the
+    /// colons, the separator and within the brackets, e.g. `(0 : 00 - 1 : 30)`.
+    pub loose_spacing: bool,
+    /// Allow whitespace, line breaks included, inside the brackets and around
+    /// the separator, for ranges wrapped across lines as in `(0:00-\n1:30)`.
+    /// Cleaning removes the whole span, line break and all.
+    pub multiline_ranges: bool,
+    /// Accept several comma-separated ranges in one bracket, summed into one
+    /// range: `(0:00-1:00, 2:00-2:30)` lasts 1:30. Parts must run forward
+    /// without overlapping. A single range is always tried first, so this never
+    /// changes how a one-range bracket is read.
+    pub range_lists: bool,
+    /// Accept interval notation with a comma and either kind of bracket on
+    /// each side, e.g. `[0:00, 1:30)`; the duration is end minus start whatever
+    /// the openness. Plain `(start-end)` ranges are read as before.
+    pub interval_notation: bool,
+    /// Ignore everything inside markdown code fences (lines opening and closing
+    /// with ```` ``` ````), so example timestamps are neither counted nor cleaned.
+    /// A fence left open runs to the end of the input.
+    pub skip_code_fences: bool,
+    /// Read a signed lone duration in brackets, `(+0:05)` or `(-0:03)`, as a
+    /// nudge listed in `adjustments` instead of a range; cleaning strips it.
+    pub nudges: bool,
+    /// Fail once this many `(` (or `[`) have been examined, guarding against inputs
+    /// crafted to be slow to scan. `None` scans the whole input.
+    pub max_candidates: Option<usize>,
+    /// Report every range as its own line, even when chained with `+`. The
+    /// total is the same either way, unless `min_duration_secs` drops lines.
+    pub ungroup: bool,
+    /// Attach the operand tree of each grouped (`+`-joined) line as `expression`.
+    pub expressions: bool,
+    /// Pass over malformed timestamp-like text instead of failing, counting
+    /// the valid ranges around it. `calculate_durations` lists what it skipped
+    /// in `warnings`. Invalid ranges, such as an end before the start, still
+    /// fail unless `skip_invalid` is set too.
+    pub skip_malformed: bool,
+    /// Drop ranges that parse but are invalid, such as an end before the start,
+    /// listing each in `warnings` like `skip_malformed` does, instead of failing.
+    /// A dropped range is left in cleaned text, and one inside a `+` chain
+    /// splits the chain around it.
+    pub skip_invalid: bool,
+    /// Also join ranges with the words in `WORD_CONNECTORS`, as in
+    /// `(0:00-1:00) and (2:00-2:30)`, wherever `+` would: grouping, bare
+    /// duration operands and cleaning.
+    pub word_connectors: bool,
+}
+
+impl ParseOptions {
+    /// The tolerant preset for casual input, `default()` plus exactly these:
+    ///
+    /// - `loose_spacing`: blanks inside the brackets, around the separator and
+    ///   around the colons, `( 0 : 00 - 1 : 30 )`
+    /// - `multiline_ranges`: ranges wrapped across lines
+    /// - `unicode_digits`: full-width and other common Unicode digits and colons
+    /// - `range_lists`: several comma-separated ranges in one bracket
+    /// - `overflow_seconds` and `warn_large_minutes`: a range with seconds or
+    ///   `M:SS` minutes over 59 is counted with a warning instead of failing
+    /// - `skip_malformed` and `skip_invalid`: malformed timestamp-like text and
+    ///   invalid ranges, such as an end before the start, are passed over and
+    ///   listed in `warnings`
+    /// - `word_connectors`: "and" and "&" join ranges like `+`
+    ///
+    /// Errors asked for with other options, such as `max_total_secs` or an
+    /// `Error` severity, still fail.
+    pub fn lenient() -> Self {
+        ParseOptions::default().with_lenient()
+    }
+
+    /// Turns on the behaviors of `lenient()`, keeping every other option as set.
+    pub fn with_lenient(self) -> Self {
+        ParseOptions {
+            loose_spacing: true,
+            multiline_ranges: true,
+            unicode_digits: true,
+            range_lists: true,
+            overflow_seconds: true,
+            warn_large_minutes: true,
+            skip_malformed: true,
+            skip_invalid: true,
+            word_connectors: true,
+            ..self
+        }
+    }
+}
+
+/// Caps on the minutes and seconds fields of range endpoints and bare
+/// durations; hours are never capped.
+///
+/// The policy only bounds fields once they are parsed. Whether a time is
+/// `H:MM:SS` or `M:SS` is decided by its colons alone, so `1:30:00` is always
+/// one and a half hours, and `90:00` is always read as minutes and seconds:
+/// 90 minutes when the minutes cap allows it, an error otherwise. Fields over
+/// 59 that a policy accepts carry over, e.g. `1:75` lasts 2:15.
+/// `warn_large_minutes` and `overflow_seconds` lift the caps on top of it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BoundsPolicy {
+    /// Minutes and seconds up to 59
+    #[default]
+    Strict,
+    /// No caps on either field
+    Lenient,
+    Custom { max_minutes: u32, max_seconds: u32 },
+}
+
+impl BoundsPolicy {
+    /// The largest minutes and seconds accepted.
+    pub fn limits(self) -> (u32, u32) {
+        match self {
+            BoundsPolicy::Strict => (59, 59),
+            BoundsPolicy::Lenient => (u32::MAX, u32::MAX),
+            BoundsPolicy::Custom { max_minutes, max_seconds } => (max_minutes, max_seconds),
+        }
+    }
+}
+
+/// Whether a content check attaches a warning to the line or fails the parse.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    #[default]
+    Warning,
+    Error,
+}
+
+/// Direction in which `ParseOutput::round_total` rounds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RoundMode {
+    /// To the next multiple, so 12:01 is 13:00 by the minute
+    #[default]
+    Up,
+    /// To the closest multiple, halfway rounding up
+    Nearest,
+}
+
+/// Direction for `ParseOutput::sort_lines`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+/// A frame rate in frames per second, above 0 and at most 240.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(try_from = "f64")]
+pub struct FrameRate(f64);
+
+impl FrameRate {
+    pub fn new(fps: f64) -> Result<Self, String> {
+        if !fps.is_finite() || fps <= 0.0 || fps > 240.0 {
+            return Err(format!("Invalid frame rate: {} (must be between 0 and 240)", fps));
+        }
+        Ok(FrameRate(fps))
+    }
+
+    pub fn get(self) -> f64 {
+        self.0
+    }
+}
+
+impl TryFrom<f64> for FrameRate {
+    type Error = String;
+
+    fn try_from(fps: f64) -> Result<Self, String> {
+        FrameRate::new(fps)
+    }
+}
+
+// Never NaN, so comparing and hashing the bits is consistent
+impl Eq for FrameRate {}
+
+impl std::hash::Hash for FrameRate {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.0.to_bits().hash(state);
+    }
 }
 
 impl Timestamp {
-    fn to_seconds(&self) -> u32 {
-        self.hours * 3600 + self.minutes * 60 + self.seconds
+    /// Seconds from zero, saturating at `u32::MAX`; `checked_seconds` tells
+    /// when it would not fit.
+    pub fn to_seconds(self) -> u32 {
+        self.checked_seconds().unwrap_or(u32::MAX)
+    }
+
+    /// Seconds from zero, or `None` when that does not fit in a `u32`, which
+    /// unbounded fields (e.g. `warn_large_minutes`) make possible.
+    pub fn checked_seconds(self) -> Option<u32> {
+        self.hours
+            .checked_mul(3600)?
+            .checked_add(self.minutes.checked_mul(60)?)?
+            .checked_add(self.seconds)
+    }
+
+    /// Splits seconds from zero into hours, minutes and seconds.
+    pub fn from_seconds(total: u32) -> Self {
+        Timestamp {
+            hours: total / 3600,
+            minutes: (total / 60) % 60,
+            seconds: total % 60,
+        }
+    }
+
+    /// Applies the range rules: minutes and seconds must not exceed 59.
+    pub fn validate(self) -> Result<(), ParseError> {
+        if self.minutes > 59 {
+            return Err(ParseError::InvalidMinutes(self.minutes));
+        }
+        if self.seconds > 59 {
+            return Err(ParseError::InvalidSeconds(self.seconds));
+        }
+        Ok(())
+    }
+
+    /// Renders the time as written in a script: `H:MM:SS` when there are
+    /// hours, otherwise `M:SS` (e.g. `1:05:09`, `0:07`). Fields are printed
+    /// as they are, without carrying overflowing seconds into minutes.
+    pub fn to_string_canonical(self) -> String {
+        if self.hours > 0 {
+            format!("{}:{:02}:{:02}", self.hours, self.minutes, self.seconds)
+        } else {
+            format!("{}:{:02}", self.minutes, self.seconds)
+        }
     }
 }
 
@@ -64,252 +1039,3201 @@ fn parse_ms(input: &str) -> IResult<&str, Timestamp> {
 }
 
 // Try H:MM:SS first, then fall back to M:SS
-fn parse_timestamp(input: &str) -> IResult<&str, Timestamp> {
+pub(crate) fn parse_timestamp(input: &str) -> IResult<&str, Timestamp> {
     alt((parse_hms, parse_ms)).parse(input)
 }
 
-// Parse any dash type: hyphen (-), en-dash (–), or em-dash (—)
+// A colon between timestamp fields with optional blanks around it, "1 : 30"
+fn parse_loose_colon(input: &str) -> IResult<&str, &str> {
+    delimited(space0, tag(":"), space0).parse(input)
+}
+
+fn parse_colon(input: &str) -> IResult<&str, &str> {
+    tag(":").parse(input)
+}
+
+// The timestamp layouts the options allow: blanks around the colons with
+// `loose_spacing`, and a two-field time read as H:MM with `hh_mm`
+fn timestamp_parser(options: &ParseOptions) -> impl Fn(&str) -> IResult<&str, Timestamp> + Copy {
+    let colon: fn(&str) -> IResult<&str, &str> =
+        if options.loose_spacing { parse_loose_colon } else { parse_colon };
+    let hh_mm = options.hh_mm;
+    move |input| {
+        let hms = (parse_number, colon, parse_number, colon, parse_number)
+            .map(|(hours, _, minutes, _, seconds)| Timestamp { hours, minutes, seconds });
+        let pair = separated_pair(parse_number, colon, parse_number).map(|(first, second)| {
+            if hh_mm {
+                Timestamp { hours: first, minutes: second, seconds: 0 }
+            } else {
+                Timestamp { hours: 0, minutes: first, seconds: second }
+            }
+        });
+        alt((hms, pair)).parse(input)
+    }
+}
+
+// Parse any dash type: hyphen (-), en-dash (–), or em-dash (—), or an
+// ellipsis (`..` / `...`). The longer ellipsis is tried first.
 fn parse_dash(input: &str) -> IResult<&str, &str> {
-    alt((tag("-"), tag("–"), tag("—"))).parse(input)
+    alt((tag("-"), tag("–"), tag("—"), tag("..."), tag(".."))).parse(input)
 }
 
-enum RangeError {
+pub(crate) enum RangeError {
     None,
     EndBeforeStart,
-    InvalidSeconds(u32),
-    InvalidMinutes(u32),
+    // The whole timestamp, so the message can suggest the intended time, and
+    // the cap it exceeds
+    InvalidSeconds(Timestamp, u32),
+    InvalidMinutes(u32, u32),
+    // A time within the caps whose seconds from zero overflow
+    TooLarge(Timestamp),
+    InvalidSpeed(f64),
+    InvalidMultiplier(u32),
+    MissingFrameRate,
+    // A range list part starting before the previous part ends
+    ListOutOfOrder,
+}
+
+impl RangeError {
+    fn category(&self) -> ErrorCategory {
+        match self {
+            RangeError::None => ErrorCategory::Other,
+            RangeError::EndBeforeStart | RangeError::ListOutOfOrder => ErrorCategory::EndBeforeStart,
+            RangeError::InvalidSeconds(..) => ErrorCategory::InvalidSeconds,
+            RangeError::InvalidMinutes(..) => ErrorCategory::InvalidMinutes,
+            RangeError::TooLarge(_) => ErrorCategory::TooLarge,
+            RangeError::InvalidSpeed(_) => ErrorCategory::InvalidSpeed,
+            RangeError::InvalidMultiplier(_) => ErrorCategory::InvalidMultiplier,
+            RangeError::MissingFrameRate => ErrorCategory::MissingFrameRate,
+        }
+    }
 }
 
 struct RangeResult {
+    start: Timestamp,
+    end: Timestamp,
     duration: u32,
     error: RangeError,
+    // Seconds from zero of each part of a range list; empty for other ranges
+    parts: Vec<(u32, u32)>,
 }
 
-fn parse_range(input: &str) -> IResult<&str, RangeResult> {
-    let (input, (start, end)) = delimited(
-        tag("("),
-        separated_pair(parse_timestamp, parse_dash, parse_timestamp),
-        tag(")"),
-    ).parse(input)?;
-    
-    // Validate minutes <= 59 (only hours can be unlimited)
-    if start.minutes > 59 {
-        return Ok((input, RangeResult { duration: 0, error: RangeError::InvalidMinutes(start.minutes) }));
-    }
-    if end.minutes > 59 {
-        return Ok((input, RangeResult { duration: 0, error: RangeError::InvalidMinutes(end.minutes) }));
+// Blanks allowed inside a range's brackets and around its separator: none
+// by default, spaces with `loose_spacing`, line breaks too with `multiline_ranges`
+fn range_gap(options: &ParseOptions) -> impl Fn(&str) -> IResult<&str, &str> + Copy {
+    let (loose, multiline) = (options.loose_spacing, options.multiline_ranges);
+    move |input| {
+        if multiline {
+            multispace0(input)
+        } else if loose {
+            space0(input)
+        } else {
+            Ok((input, &input[..0]))
+        }
     }
-    
-    // Validate seconds <= 59
-    if start.seconds > 59 {
-        return Ok((input, RangeResult { duration: 0, error: RangeError::InvalidSeconds(start.seconds) }));
+}
+
+// The "start-end" inside the brackets, with `loose` blanks around the colons
+fn range_bounds(options: &ParseOptions) -> impl Fn(&str) -> IResult<&str, (Timestamp, Timestamp)> + Copy {
+    let gap = range_gap(options);
+    let timestamp = timestamp_parser(options);
+    move |input| separated_pair(timestamp, delimited(gap, parse_dash, gap), timestamp).parse(input)
+}
+
+// Parse "(start-end)"; with `loose` spacing, blanks may also appear inside the
+// brackets, around the separator and around the colons: "( 0 : 00 - 1 : 30 )".
+// With `multiline`, the gaps inside the brackets and around the separator may
+// also hold line breaks: "(0:00-\n1:30)"
+fn parse_range<'a>(input: &'a str, options: &ParseOptions) -> IResult<&'a str, RangeResult> {
+    let gap = range_gap(options);
+    let (input, (start, end)) =
+        delimited((tag("("), gap), range_bounds(options), (gap, tag(")"))).parse(input)?;
+    Ok((input, check_bounds(start, end, options)))
+}
+
+// Parse "(0:00-1:00, 2:00-2:30)", several ranges in one bracket counted as one
+// with their durations summed. It spans the first start to the last end, and
+// the first invalid part makes the whole bracket invalid.
+fn parse_range_list<'a>(input: &'a str, options: &ParseOptions) -> IResult<&'a str, RangeResult> {
+    let gap = range_gap(options);
+    let (input, parts) = delimited(
+        (tag("("), gap),
+        separated_list1((gap, tag(","), space0, gap), range_bounds(options)),
+        (gap, tag(")")),
+    )
+    .parse(input)?;
+
+    let (start, end) = (parts[0].0, parts[parts.len() - 1].1);
+    let mut duration = 0u32;
+    let mut spans: Vec<(u32, u32)> = Vec::new();
+    for (part_start, part_end) in parts {
+        let part = check_bounds(part_start, part_end, options);
+        if !matches!(part.error, RangeError::None) {
+            return Ok((input, part));
+        }
+        let span = (part_start.to_seconds(), part_end.to_seconds());
+        // Parts run forward without overlapping, so the list covers no time twice
+        if spans.last().is_some_and(|&(_, previous_end)| span.0 < previous_end) {
+            let error = RangeError::ListOutOfOrder;
+            return Ok((input, RangeResult { start, end, duration: 0, error, parts: Vec::new() }));
+        }
+        duration = duration.saturating_add(part.duration);
+        spans.push(span);
     }
-    if end.seconds > 59 {
-        return Ok((input, RangeResult { duration: 0, error: RangeError::InvalidSeconds(end.seconds) }));
+    Ok((input, RangeResult { start, end, duration, error: RangeError::None, parts: spans }))
+}
+
+// Parse interval notation, "[0:00, 1:30)": `[` or `(` to open, `]` or `)` to
+// close, with a comma between the times
+fn parse_interval<'a>(input: &'a str, options: &ParseOptions) -> IResult<&'a str, RangeResult> {
+    let gap = range_gap(options);
+    let timestamp = timestamp_parser(options);
+    let (input, (start, end)) = delimited(
+        (alt((tag("["), tag("("))), gap),
+        separated_pair(timestamp, (gap, tag(","), space0, gap), timestamp),
+        (gap, alt((tag("]"), tag(")")))),
+    )
+    .parse(input)?;
+    Ok((input, check_bounds(start, end, options)))
+}
+
+// Validates the times of a range and computes its duration
+fn check_bounds(start: Timestamp, end: Timestamp, options: &ParseOptions) -> RangeResult {
+    if let Some(error) = [start, end].into_iter().find_map(|timestamp| field_error(timestamp, options)) {
+        return RangeResult { start, end, duration: 0, error, parts: Vec::new() };
     }
     
     let start_secs = start.to_seconds();
     let end_secs = end.to_seconds();
     
     if end_secs < start_secs {
-        Ok((input, RangeResult { duration: 0, error: RangeError::EndBeforeStart }))
+        RangeResult { start, end, duration: 0, error: RangeError::EndBeforeStart, parts: Vec::new() }
     } else {
-        Ok((input, RangeResult { duration: end_secs - start_secs, error: RangeError::None }))
+        RangeResult { start, end, duration: end_secs - start_secs, error: RangeError::None, parts: Vec::new() }
     }
 }
 
-// Represents a parsed timestamp range with its position and text in the input
-struct ParsedRange {
-    start_pos: usize,
-    end_pos: usize,
-    text: String,
-    duration: u32,
-    error: RangeError,
+// Validate minutes and seconds against the bounds policy (only hours are
+// unlimited). `warn_large_minutes` lets M:SS minutes overflow, flagged later by
+// `large_minutes_warning`; `overflow_seconds` carries seconds into the minutes
+// (`to_seconds` already does), flagged by `overflow_seconds_warning`
+fn field_error(timestamp: Timestamp, options: &ParseOptions) -> Option<RangeError> {
+    let (max_minutes, max_seconds) = options.bounds.limits();
+    let minutes_checked = !(options.warn_large_minutes && timestamp.hours == 0);
+    if timestamp.minutes > max_minutes && minutes_checked {
+        return Some(RangeError::InvalidMinutes(timestamp.minutes, max_minutes));
+    }
+    if timestamp.seconds > max_seconds && !options.overflow_seconds {
+        return Some(RangeError::InvalidSeconds(timestamp, max_seconds));
+    }
+    // Endpoints may mix layouts, e.g. (1:30:00-95:00), and are compared in
+    // seconds, so each must convert on its own
+    timestamp.checked_seconds().is_none().then_some(RangeError::TooLarge(timestamp))
 }
 
-fn find_all_ranges(input: &str) -> Result<Vec<ParsedRange>, String> {
-    let mut ranges = Vec::new();
-    let mut search_start = 0;
-    
-    // Pattern to detect things that look like timestamp ranges (includes unicode dashes)
-    let timestamp_pattern = Regex::new(r"\([^)]*:[^)]*[-–—][^)]*:[^)]*\)").unwrap();
+// A signed lone duration for `nudges`, "(+0:05)" or "(-0:03)", with its sign
+fn parse_nudge<'a>(input: &'a str, options: &ParseOptions) -> IResult<&'a str, (i64, Timestamp)> {
+    let sign = satisfy(|c| matches!(c, '+' | '-' | '−')).map(|c| if c == '+' { 1 } else { -1 });
+    let (rest, (_, sign, timestamp, _)) = (tag("("), sign, timestamp_parser(options), tag(")")).parse(input)?;
+    Ok((rest, (sign, timestamp)))
+}
+
+// Parse a frame count endpoint, e.g. "240f"
+fn parse_frames(input: &str) -> IResult<&str, u64> {
+    let (input, (frames, _)) = (map_res(digit1, |s: &str| s.parse::<u64>()), tag("f")).parse(input)?;
+    Ok((input, frames))
+}
+
+// Parse a frames-only range like "(0f-240f)", converted at `frame_rate`
+fn parse_frame_range(input: &str, frame_rate: Option<FrameRate>) -> IResult<&str, RangeResult> {
+    let (input, (start, end)) = delimited(
+        tag("("),
+        separated_pair(parse_frames, parse_dash, parse_frames),
+        tag(")"),
+    ).parse(input)?;
+
+    let Some(fps) = frame_rate.map(FrameRate::get) else {
+        let zero = Timestamp::from_seconds(0);
+        return Ok((input, RangeResult { start: zero, end: zero, duration: 0, error: RangeError::MissingFrameRate, parts: Vec::new() }));
+    };
+    let to_seconds = |frames: u64| (frames as f64 / fps).min(u32::MAX as f64);
+    let (start_secs, end_secs) = (to_seconds(start), to_seconds(end));
+    let start_time = Timestamp::from_seconds(start_secs.floor() as u32);
+    let end_time = Timestamp::from_seconds(end_secs.ceil() as u32);
+
+    if end < start {
+        Ok((input, RangeResult { start: start_time, end: end_time, duration: 0, error: RangeError::EndBeforeStart, parts: Vec::new() }))
+    } else {
+        let duration = (end_secs - start_secs).round() as u32;
+        Ok((input, RangeResult { start: start_time, end: end_time, duration, error: RangeError::None, parts: Vec::new() }))
+    }
+}
+
+// Any range the options allow: a single range first, then a comma-separated
+// list, frame counts and interval notation when enabled
+fn parse_any_range<'a>(input: &'a str, options: &ParseOptions) -> IResult<&'a str, RangeResult> {
+    parse_range(input, options)
+        .or_else(|e| if options.range_lists { parse_range_list(input, options) } else { Err(e) })
+        .or_else(|e| if options.frame_ranges { parse_frame_range(input, options.frame_rate) } else { Err(e) })
+        .or_else(|e| if options.interval_notation { parse_interval(input, options) } else { Err(e) })
+}
+
+/// Bounds of the `@<factor>` speed modifier, see `ParseOptions::speed_factors`.
+pub const SPEED_FACTOR_RANGE: std::ops::RangeInclusive<f64> = 0.1..=10.0;
+
+// Parse an "@0.5x" speed modifier directly after a range; the "x" is optional
+fn parse_speed_factor(input: &str, decimal_comma: bool) -> IResult<&str, f64> {
+    let decimal_mark = |c: char| c == '.' || (decimal_comma && c == ',');
+    let (rest, (_, factor, _)) = (
+        tag("@"),
+        map_res(
+            recognize((digit1, opt((satisfy(decimal_mark), digit1)))),
+            |s: &str| s.replace(',', ".").parse::<f64>(),
+        ),
+        opt(tag("x")),
+    )
+        .parse(input)?;
+    Ok((rest, factor))
+}
+
+// A range played at `factor` speed lasts duration / factor, e.g. 0.5x doubles it
+fn apply_speed_factor(result: &mut RangeResult, factor: f64) {
+    if !matches!(result.error, RangeError::None) {
+        return;
+    }
+    if SPEED_FACTOR_RANGE.contains(&factor) {
+        result.duration = (result.duration as f64 / factor).round() as u32;
+    } else {
+        result.duration = 0;
+        result.error = RangeError::InvalidSpeed(factor);
+    }
+}
+
+/// Bounds of the `xN` repeat count, see `ParseOptions::multipliers`.
+pub const MULTIPLIER_RANGE: std::ops::RangeInclusive<u32> = 1..=100;
+
+// Parse an "x4" repeat count after a range, directly or after blanks; "×" and
+// "X" also work. A following letter or digit makes it prose, as in "x4k"
+fn parse_multiplier(input: &str) -> IResult<&str, u32> {
+    let (rest, (_, _, count, _)) = (
+        space0,
+        satisfy(|c| matches!(c, 'x' | 'X' | '×')),
+        map_res(digit1, |s: &str| s.parse::<u32>()),
+        not(satisfy(|c: char| c.is_alphanumeric() || c == '_')),
+    )
+        .parse(input)?;
+    Ok((rest, count))
+}
+
+// A range repeated `count` times lasts `count` times as long
+fn apply_multiplier(result: &mut RangeResult, count: u32) {
+    if !matches!(result.error, RangeError::None) {
+        return;
+    }
+    if MULTIPLIER_RANGE.contains(&count) {
+        result.duration = result.duration.saturating_mul(count);
+    } else {
+        result.duration = 0;
+        result.error = RangeError::InvalidMultiplier(count);
+    }
+}
+
+// Represents a parsed timestamp range with its position and text in the input
+pub(crate) struct ParsedRange {
+    pub(crate) start_pos: usize,
+    pub(crate) end_pos: usize,
+    pub(crate) text: String,
+    pub(crate) start: Timestamp,
+    // None for bare duration operands
+    pub(crate) end: Option<Timestamp>,
+    pub(crate) duration: u32,
+    pub(crate) error: RangeError,
+    // A bare duration operand rather than a (start-end) range
+    pub(crate) bare: bool,
+    // Seconds from zero of each part of a range list; empty for other ranges
+    pub(crate) parts: Vec<(u32, u32)>,
+}
+
+// Parse a " + M:SS" operand, returning the offset of the timestamp and the rest.
+// A word connector needs a blank after it, so "and0:30" is not taken for one
+fn parse_bare_operand<'a>(input: &'a str, options: &ParseOptions) -> IResult<&'a str, (usize, Timestamp)> {
+    let (rest, _) = space0(input)?;
+    let (rest, _) = if options.word_connectors {
+        alt((tag("+"), terminated(alt((tag("and"), tag("&"))), peek(space1)))).parse(rest)?
+    } else {
+        tag("+").parse(rest)?
+    };
+    let (rest, _) = space0(rest)?;
+    let offset = input.len() - rest.len();
+    let (rest, timestamp) = timestamp_parser(options)(rest)?;
+    Ok((rest, (offset, timestamp)))
+}
+
+// Scan for bare duration operands chained after the range ending at `pos`
+fn find_bare_operands(input: &str, mut pos: usize, options: &ParseOptions, ranges: &mut Vec<ParsedRange>) -> usize {
+    while let Ok((rest, (offset, timestamp))) = parse_bare_operand(&input[pos..], options) {
+        let start_pos = pos + offset;
+        let end_pos = input.len() - rest.len();
+        let (max_minutes, max_seconds) = options.bounds.limits();
+        let error = if timestamp.minutes > max_minutes {
+            RangeError::InvalidMinutes(timestamp.minutes, max_minutes)
+        } else if timestamp.seconds > max_seconds {
+            RangeError::InvalidSeconds(timestamp, max_seconds)
+        } else if timestamp.checked_seconds().is_none() {
+            RangeError::TooLarge(timestamp)
+        } else {
+            RangeError::None
+        };
+        let duration = match error {
+            RangeError::None => timestamp.to_seconds(),
+            _ => 0,
+        };
+        ranges.push(ParsedRange {
+            start_pos,
+            end_pos,
+            text: input[start_pos..end_pos].to_string(),
+            start: timestamp,
+            end: None,
+            duration,
+            error,
+            bare: true,
+            parts: Vec::new(),
+        });
+        pos = end_pos;
+    }
+    pos
+}
+
+// Map a Unicode decimal digit from a common script to its ASCII digit
+fn ascii_digit(c: char) -> Option<char> {
+    let zero = match c {
+        '０'..='９' => '０',
+        '٠'..='٩' => '٠', // Arabic-Indic
+        '۰'..='۹' => '۰', // Extended Arabic-Indic
+        '०'..='९' => '०', // Devanagari
+        '০'..='৯' => '০', // Bengali
+        _ => return None,
+    };
+    char::from_digit(c as u32 - zero as u32, 10)
+}
+
+// Rewrite Unicode digits and the full-width colon to ASCII. Returns the new text
+// and, for each of its byte offsets (plus the end), the offset in the original.
+// `None` when there is nothing to rewrite.
+fn normalize_digits(input: &str) -> Option<(String, Vec<usize>)> {
+    if input.is_ascii() || !input.chars().any(|c| c == '：' || ascii_digit(c).is_some()) {
+        return None;
+    }
+
+    let mut normalized = String::with_capacity(input.len());
+    let mut offsets = Vec::with_capacity(input.len() + 1);
+    for (pos, c) in input.char_indices() {
+        let mapped = if c == '：' { ':' } else { ascii_digit(c).unwrap_or(c) };
+        normalized.push(mapped);
+        offsets.extend(std::iter::repeat_n(pos, mapped.len_utf8()));
+    }
+    offsets.push(input.len());
+    Some((normalized, offsets))
+}
+
+// A timestamp-like span that failed to parse, with the error it raises
+pub(crate) struct MalformedSpan {
+    pub(crate) start_pos: usize,
+    pub(crate) end_pos: usize,
+    pub(crate) category: ErrorCategory,
+    pub(crate) message: String,
+}
+
+// Every range in the input, plus the malformed spans that make parsing fail
+pub(crate) struct ScanResult {
+    pub(crate) ranges: Vec<ParsedRange>,
+    pub(crate) malformed: Vec<MalformedSpan>,
+    pub(crate) adjustments: Vec<Adjustment>,
+    // Byte offsets of the backslashes escaping literal ranges, "\(0:00-1:00\)",
+    // which cleaning drops
+    pub(crate) escapes: Vec<usize>,
+    // Set when the scan stopped early at `max_candidates`
+    pub(crate) aborted: Option<ScriptError>,
+}
+
+impl ScanResult {
+    // Messages of the malformed spans `skip_malformed` passes over, which then
+    // no longer fail `into_ranges`, and of the invalid ranges `skip_invalid`
+    // drops, in input order
+    fn take_skipped(&mut self, options: &ParseOptions) -> Vec<String> {
+        let mut skipped: Vec<(usize, String)> = Vec::new();
+        if options.skip_malformed {
+            skipped.extend(self.malformed.drain(..).map(|span| (span.start_pos, span.message)));
+        }
+        if options.skip_invalid {
+            self.ranges.retain(|range| match range_error_message(range) {
+                Some(message) => {
+                    skipped.push((range.start_pos, message));
+                    false
+                }
+                None => true,
+            });
+        }
+        skipped.sort_by_key(|(pos, _)| *pos);
+        skipped.into_iter().map(|(_, message)| message).collect()
+    }
+
+    // The ranges, or the error of an aborted scan or the first malformed span
+    fn into_ranges(self) -> Result<Vec<ParsedRange>, ScriptError> {
+        if let Some(error) = self.aborted {
+            return Err(error);
+        }
+        match self.malformed.into_iter().next() {
+            Some(span) => Err(ScriptError::new(span.category, span.message)),
+            None => Ok(self.ranges),
+        }
+    }
+}
+
+// The ranges, with the messages of malformed spans passed over under
+// `skip_malformed`
+pub(crate) fn find_all_ranges(
+    input: &str,
+    options: &ParseOptions,
+) -> Result<(Vec<ParsedRange>, Vec<String>), ScriptError> {
+    let mut scan = scan_script(input, options);
+    let skipped = scan.take_skipped(options);
+    Ok((scan.into_ranges()?, skipped))
+}
+
+// Scan without failing on malformed text, so callers can act on those spans
+pub(crate) fn scan_script(input: &str, options: &ParseOptions) -> ScanResult {
+    let mut scan = if options.unicode_digits
+        && let Some((normalized, offsets)) = normalize_digits(input)
+    {
+        let mut scan = scan_ranges(&normalized, options);
+        for range in &mut scan.ranges {
+            range.start_pos = offsets[range.start_pos];
+            range.end_pos = offsets[range.end_pos];
+            range.text = input[range.start_pos..range.end_pos].to_string();
+        }
+        // Each message quotes its span, so swap in the original text of it
+        for span in &mut scan.malformed {
+            let normalized_text = &normalized[span.start_pos..span.end_pos];
+            span.start_pos = offsets[span.start_pos];
+            span.end_pos = offsets[span.end_pos];
+            span.message = span.message.replacen(normalized_text, &input[span.start_pos..span.end_pos], 1);
+        }
+        for adjustment in &mut scan.adjustments {
+            adjustment.span = offsets[adjustment.span.start]..offsets[adjustment.span.end];
+            adjustment.text = input[adjustment.span.clone()].to_string();
+        }
+        for escape in &mut scan.escapes {
+            *escape = offsets[*escape];
+        }
+        scan
+    } else {
+        scan_ranges(input, options)
+    };
+
+    if options.skip_code_fences {
+        let fences = code_fences(input);
+        let fenced = |start: usize, end: usize| {
+            fences.iter().any(|fence| start < fence.end && end > fence.start)
+        };
+        scan.ranges.retain(|range| !fenced(range.start_pos, range.end_pos));
+        scan.malformed.retain(|span| !fenced(span.start_pos, span.end_pos));
+        scan.adjustments.retain(|adjustment| !fenced(adjustment.span.start, adjustment.span.end));
+        scan.escapes.retain(|&escape| !fenced(escape, escape + 1));
+    }
+    scan
+}
+
+// Byte spans of markdown code fences, from the opening ``` line through the
+// closing one; an unclosed fence runs to the end of the input
+fn code_fences(input: &str) -> Vec<Range<usize>> {
+    let mut fences = Vec::new();
+    let mut open = None;
+    let mut pos = 0;
+    for line in input.split_inclusive('\n') {
+        if line.trim_start().starts_with("```") {
+            match open.take() {
+                Some(start) => fences.push(start..pos + line.len()),
+                None => open = Some(pos),
+            }
+        }
+        pos += line.len();
+    }
+    if let Some(start) = open {
+        fences.push(start..input.len());
+    }
+    fences
+}
+
+// Pattern to detect things that look like timestamp ranges (includes unicode dashes
+// and `..`). Both sides of the separator may only hold digits, colons, dashes,
+// dots, commas and spaces, so prose such as "(camera pans left-right: slow)" is
+// never a candidate; this also keeps an outer bracket in prose, like
+// "(see the intro (0:00-1:00) for details)", from swallowing a nested range.
+// The second branch catches dotted times such as "(0.00..1.30)", which would
+// otherwise be ambiguous with the ellipsis separator.
+// Anchored and free of brackets inside, so each check stops at the next bracket
+// instead of scanning the rest of the input; compiled once for all scans.
+static TIMESTAMP_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    let side = r"[\d\s.,:\-–—]*";
+    Regex::new(&format!(
+        r"^(?:\({side}:{side}(?:[-–—]|\.\.){side}:{side}\)|\(\s*\d+\.\d+\s*\.{{2,3}}\s*\d+\.\d+\s*\))"
+    ))
+    .unwrap()
+});
+
+// The start of a range, "(0:00-1:30", running to the end of the line or the next
+// bracket. Unterminated when it does not stop at a closing bracket.
+static UNTERMINATED_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\(\d+(?::\d+){1,2}[ \t]*(?:[-–—]|\.{2,3})[^()\n]*").unwrap()
+});
+
+// Whether the text after the start of a range reaches its closing bracket on
+// the same line, past any bracketed asides, "(2:00 - 3:00 (approx))"
+fn closes_after_groups(mut rest: &str) -> bool {
+    loop {
+        if rest.starts_with(')') {
+            return true;
+        }
+        if !rest.starts_with('(') {
+            return false;
+        }
+        let Some(close) = rest[1..].find(['(', ')', '\n']).filter(|&i| rest[1 + i..].starts_with(')')) else {
+            return false;
+        };
+        rest = &rest[close + 2..];
+        rest = rest.trim_start_matches(|c| !matches!(c, '(' | ')' | '\n'));
+    }
+}
+
+// Interval notation candidates for `interval_notation`: digits, colons, dots and
+// blanks around a single comma between mixed brackets, "[0:00, 1:30)"
+static INTERVAL_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^[\[(][\d\s.:]*,[\d\s.:]*[\])]").unwrap()
+});
+
+// 1-based line and column (in chars) of byte offsets visited in increasing
+// order, counting only the text since the previous one, so a scan reporting an
+// error at every bracket of a long line stays linear
+struct LineCursor<'a> {
+    input: &'a str,
+    pos: usize,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> LineCursor<'a> {
+    fn new(input: &'a str) -> Self {
+        LineCursor { input, pos: 0, line: 1, column: 1 }
+    }
+
+    fn locate(&mut self, pos: usize) -> (usize, usize) {
+        let passed = &self.input[self.pos..pos];
+        match passed.rfind('\n') {
+            Some(last) => {
+                self.line += passed.matches('\n').count();
+                self.column = passed[last + 1..].chars().count() + 1;
+            }
+            None => self.column += passed.chars().count(),
+        }
+        self.pos = pos;
+        (self.line, self.column)
+    }
+}
+
+/// Bracket count from which the `parallel` feature parses ranges on the rayon
+/// thread pool; smaller inputs are not worth the overhead.
+#[cfg(feature = "parallel")]
+pub const PARALLEL_MIN_BRACKETS: usize = 1024;
+
+// A range parsed ahead of the scan: its length in bytes and result
+type Prepared = Option<(usize, RangeResult)>;
+
+// Parses a range at every candidate bracket in parallel, up to `max_candidates` of them.
+// The scan then looks these up in order instead of parsing, so it skips
+// exactly the same text as the sequential scan. `None` below `min_brackets`.
+#[cfg(feature = "parallel")]
+fn prepare_ranges(input: &str, options: &ParseOptions, min_brackets: usize) -> Option<Vec<(usize, Prepared)>> {
+    use rayon::prelude::*;
 
-    while let Some(paren_pos) = input[search_start..].find('(') {
+    let limit = options.max_candidates.map_or(usize::MAX, |max| max.saturating_add(1));
+    let is_candidate = |c: char| c == '(' || (options.interval_notation && c == '[');
+    let positions: Vec<usize> = input.match_indices(is_candidate).map(|(pos, _)| pos).take(limit).collect();
+    if positions.len() < min_brackets {
+        return None;
+    }
+    Some(
+        positions
+            .into_par_iter()
+            .map(|pos| {
+                let remaining = &input[pos..];
+                let parsed = parse_any_range(remaining, options)
+                    .ok()
+                    .map(|(rest, result)| (remaining.len() - rest.len(), result));
+                (pos, parsed)
+            })
+            .collect(),
+    )
+}
+
+fn scan_ranges(input: &str, options: &ParseOptions) -> ScanResult {
+    #[cfg(feature = "parallel")]
+    let prepared = prepare_ranges(input, options, PARALLEL_MIN_BRACKETS);
+    #[cfg(not(feature = "parallel"))]
+    let prepared = None;
+    scan_prepared_ranges(input, options, prepared)
+}
+
+// The scan, looking ranges up in `prepared` when the brackets were parsed ahead
+fn scan_prepared_ranges(
+    input: &str,
+    options: &ParseOptions,
+    prepared: Option<Vec<(usize, Prepared)>>,
+) -> ScanResult {
+    let mut ranges = Vec::new();
+    let mut malformed = Vec::new();
+    let mut adjustments = Vec::new();
+    let mut escapes = Vec::new();
+    let mut search_start = 0;
+    let mut candidates = 0;
+    let mut cursor = LineCursor::new(input);
+
+    let mut prepared = prepared.map(|ranges| ranges.into_iter().peekable());
+    let mut parse_at = |pos: usize| -> Option<(&str, RangeResult)> {
+        let Some(prepared) = prepared.as_mut() else {
+            return parse_any_range(&input[pos..], options).ok();
+        };
+        // Brackets inside an accepted range are never visited; pass over them
+        while prepared.next_if(|(at, _)| *at < pos).is_some() {}
+        let (_, parsed) = prepared.next_if(|(at, _)| *at == pos)?;
+        parsed.map(|(len, result)| (&input[pos + len..], result))
+    };
+    
+    let is_candidate = |c: char| c == '(' || (options.interval_notation && c == '[');
+    while let Some(paren_pos) = input[search_start..].find(is_candidate) {
         let abs_start = search_start + paren_pos;
+
+        // Bound the work an adversarial input full of brackets can cause
+        candidates += 1;
+        if options.max_candidates.is_some_and(|max| candidates > max) {
+            let (line, column) = cursor.locate(abs_start);
+            let aborted = Some(ScriptError::new(
+                ErrorCategory::TooManyBrackets,
+                format!(
+                    "Too many brackets to scan (limit {}), stopped at line {}, column {}",
+                    candidates - 1,
+                    line,
+                    column
+                ),
+            ));
+            return ScanResult { ranges, malformed, adjustments, escapes, aborted };
+        }
         let remaining = &input[abs_start..];
-        
-        if let Ok((rest, result)) = parse_range(remaining) {
+        // No pattern below matches past the first parenthesis after the opening
+        // bracket, so they only see the text up to it: a long run of digits and
+        // colons costs one pass, however many patterns look at it
+        let bounded = match remaining[1..].find(['(', ')']) {
+            Some(next) => &remaining[..next + 2],
+            None => remaining,
+        };
+
+        if input[..abs_start].ends_with('\\') {
+            // An escaped bracket, "\(0:00-1:00)", is a literal example: never a
+            // range and never malformed. When it escapes a whole range, cleaning
+            // drops its backslashes, the closing one included
+            match escaped_range(bounded, options) {
+                Some((len, closing_escape)) => {
+                    escapes.push(abs_start - 1);
+                    escapes.extend(closing_escape.map(|offset| abs_start + offset));
+                    search_start = abs_start + len;
+                }
+                None => search_start = abs_start + 1,
+            }
+        } else if let Some((mut rest, mut result)) = parse_at(abs_start) {
+            if options.speed_factors
+                && let Ok((after, factor)) = parse_speed_factor(rest, options.decimal_comma)
+            {
+                rest = after;
+                apply_speed_factor(&mut result, factor);
+            }
+            if options.multipliers
+                && let Ok((after, count)) = parse_multiplier(rest)
+            {
+                rest = after;
+                apply_multiplier(&mut result, count);
+            }
             let range_len = remaining.len() - rest.len();
             let text = input[abs_start..abs_start + range_len].to_string();
             ranges.push(ParsedRange {
                 start_pos: abs_start,
                 end_pos: abs_start + range_len,
                 text,
+                start: result.start,
+                end: Some(result.end),
                 duration: result.duration,
                 error: result.error,
+                bare: false,
+                parts: result.parts,
             });
             search_start = abs_start + range_len;
-        } else if let Some(m) = timestamp_pattern.find(remaining) {
-            // Looks like a timestamp but failed to parse - malformed
-            if m.start() == 0 {
-                return Err(format!("Malformed timestamp: {}", m.as_str()));
+            if options.bare_durations {
+                search_start = find_bare_operands(input, search_start, options, &mut ranges);
             }
-            search_start = abs_start + 1;
+        } else if options.nudges
+            && let Ok((rest, (sign, timestamp))) = parse_nudge(remaining, options)
+        {
+            let end_pos = input.len() - rest.len();
+            let text = &input[abs_start..end_pos];
+            match field_error(timestamp, options) {
+                Some(error) => malformed.push(MalformedSpan {
+                    start_pos: abs_start,
+                    end_pos,
+                    category: error.category(),
+                    message: match error {
+                        RangeError::InvalidMinutes(mins, max) => {
+                            format!("Invalid adjustment: {} (minutes {} exceeds {})", text, mins, max)
+                        }
+                        RangeError::InvalidSeconds(timestamp, max) => format!(
+                            "Invalid adjustment: {} (seconds {} exceeds {})",
+                            text, timestamp.seconds, max
+                        ),
+                        _ => format!("Invalid adjustment: {}", text),
+                    },
+                }),
+                None => adjustments.push(Adjustment {
+                    line: cursor.locate(abs_start).0,
+                    text: text.to_string(),
+                    delta: DurationDelta::new(sign * timestamp.to_seconds() as i64),
+                    span: abs_start..end_pos,
+                }),
+            }
+            search_start = end_pos;
+        } else if let Some(m) = TIMESTAMP_PATTERN.find(bounded)
+            && m.as_str().contains(|c: char| c.is_ascii_digit())
+        {
+            // Looks like a timestamp but failed to parse - malformed
+            let message = if m.as_str().contains(':') {
+                format!("Malformed timestamp: {}", m.as_str())
+            } else {
+                format!(
+                    "Malformed timestamp: {} (use ':' inside timestamps, e.g. (0:00..1:30))",
+                    m.as_str()
+                )
+            };
+            malformed.push(MalformedSpan {
+                start_pos: abs_start,
+                end_pos: abs_start + m.end(),
+                category: ErrorCategory::Malformed,
+                message,
+            });
+            search_start = abs_start + m.end();
+        } else if let Some(m) = UNTERMINATED_PATTERN.find(bounded)
+            && !closes_after_groups(&remaining[m.end()..])
+        {
+            // A range that opens but never closes, e.g. "(0:00-1:30" - a paste error
+            let (line, column) = cursor.locate(abs_start);
+            let text = m.as_str().trim_end();
+            malformed.push(MalformedSpan {
+                start_pos: abs_start,
+                end_pos: abs_start + text.len(),
+                category: ErrorCategory::Malformed,
+                message: format!(
+                    "Unterminated timestamp range at line {}, column {}: {}",
+                    line, column, text
+                ),
+            });
+            search_start = abs_start + m.end();
+        } else if options.interval_notation
+            && let Some(m) = INTERVAL_PATTERN.find(bounded)
+            && m.as_str().contains(':')
+        {
+            // Interval notation that failed to parse, e.g. "[0:00, 1::30)"
+            malformed.push(MalformedSpan {
+                start_pos: abs_start,
+                end_pos: abs_start + m.end(),
+                category: ErrorCategory::Malformed,
+                message: format!("Malformed interval: {} (use e.g. [0:00, 1:30))", m.as_str()),
+            });
+            search_start = abs_start + m.end();
         } else {
             search_start = abs_start + 1;
         }
     }
 
-    Ok(ranges)
+    ScanResult { ranges, malformed, adjustments, escapes, aborted: None }
+}
+
+// The length of an escaped range after its opening backslash, "(0:00-1:00)"
+// or "(0:00-1:00\)", and the offset of the backslash escaping its closing
+// bracket, if any
+fn escaped_range(text: &str, options: &ParseOptions) -> Option<(usize, Option<usize>)> {
+    if let Ok((rest, _)) = parse_any_range(text, options) {
+        return Some((text.len() - rest.len(), None));
+    }
+    let escape = text.find('\\')?;
+    let closing = text[escape + 1..].chars().next().filter(|c| matches!(c, ')' | ']'))?;
+    let unescaped = format!("{}{}", &text[..escape], closing);
+    let (rest, _) = parse_any_range(&unescaped, options).ok()?;
+    rest.is_empty().then_some((escape + 2, Some(escape)))
+}
+
+// The time an overflowing timestamp most likely meant, carrying the extra
+// seconds into the minutes (and hours): 0:90 -> 1:30, 59:75 -> 1:00:15
+fn suggested_time(timestamp: Timestamp) -> String {
+    let total = timestamp.hours as u64 * 3600 + timestamp.minutes as u64 * 60 + timestamp.seconds as u64;
+    let normalized = Timestamp {
+        hours: (total / 3600) as u32,
+        minutes: (total / 60 % 60) as u32,
+        seconds: (total % 60) as u32,
+    };
+    normalized.to_string_canonical()
+}
+
+/// Error for a script without any range, where one is required.
+pub const NO_TIMESTAMPS_MESSAGE: &str = "No valid timestamps found";
+
+/// Kinds of parse failure, for aggregate error statistics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCategory {
+    /// Timestamp-like text that does not parse, unterminated ranges included
+    Malformed,
+    EndBeforeStart,
+    InvalidMinutes,
+    InvalidSeconds,
+    InvalidSpeed,
+    InvalidMultiplier,
+    MissingFrameRate,
+    /// A time whose seconds from zero do not fit in a `u32`
+    TooLarge,
+    /// A range over `max_duration_secs` with error severity
+    TooLong,
+    /// A zero-length range with error severity
+    ZeroLength,
+    /// A total over `max_total_secs`
+    TotalTooLong,
+    TooManyBrackets,
+    NoTimestamps,
+    Other,
+}
+
+impl ErrorCategory {
+    pub const ALL: [ErrorCategory; 14] = [
+        ErrorCategory::Malformed,
+        ErrorCategory::EndBeforeStart,
+        ErrorCategory::InvalidMinutes,
+        ErrorCategory::InvalidSeconds,
+        ErrorCategory::InvalidSpeed,
+        ErrorCategory::InvalidMultiplier,
+        ErrorCategory::MissingFrameRate,
+        ErrorCategory::TooLarge,
+        ErrorCategory::TooLong,
+        ErrorCategory::ZeroLength,
+        ErrorCategory::TotalTooLong,
+        ErrorCategory::TooManyBrackets,
+        ErrorCategory::NoTimestamps,
+        ErrorCategory::Other,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCategory::Malformed => "malformed",
+            ErrorCategory::EndBeforeStart => "end_before_start",
+            ErrorCategory::InvalidMinutes => "invalid_minutes",
+            ErrorCategory::InvalidSeconds => "invalid_seconds",
+            ErrorCategory::InvalidSpeed => "invalid_speed",
+            ErrorCategory::InvalidMultiplier => "invalid_multiplier",
+            ErrorCategory::MissingFrameRate => "missing_frame_rate",
+            ErrorCategory::TooLarge => "too_large",
+            ErrorCategory::TooLong => "too_long",
+            ErrorCategory::ZeroLength => "zero_length",
+            ErrorCategory::TotalTooLong => "total_too_long",
+            ErrorCategory::TooManyBrackets => "too_many_brackets",
+            ErrorCategory::NoTimestamps => "no_timestamps",
+            ErrorCategory::Other => "other",
+        }
+    }
+}
+
+/// Why a script was rejected: the message shown to the user, and the category
+/// it counts under, set where the error is raised.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptError {
+    pub category: ErrorCategory,
+    pub message: String,
+}
+
+impl ScriptError {
+    pub fn new(category: ErrorCategory, message: impl Into<String>) -> Self {
+        ScriptError {
+            category,
+            message: message.into(),
+        }
+    }
+
+    // The same error with context, such as "Line 2: ", put before the message
+    fn prefixed(self, prefix: &str) -> Self {
+        ScriptError {
+            message: format!("{}{}", prefix, self.message),
+            ..self
+        }
+    }
+}
+
+impl std::fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ScriptError {}
+
+impl From<ScriptError> for String {
+    fn from(error: ScriptError) -> Self {
+        error.message
+    }
+}
+
+// The error failing a script with this range, if it is invalid
+pub(crate) fn range_error(range: &ParsedRange) -> Option<ScriptError> {
+    range_error_message(range).map(|message| ScriptError::new(range.error.category(), message))
+}
+
+pub(crate) fn range_error_message(range: &ParsedRange) -> Option<String> {
+    match &range.error {
+        RangeError::EndBeforeStart => {
+            Some(format!("Invalid timestamp range: {} (end time is before start time)", range.text))
+        }
+        RangeError::InvalidMinutes(mins, max) => Some(format!(
+            "Invalid timestamp range: {} (minutes {} exceeds {}, use H:MM:SS format)",
+            range.text, mins, max
+        )),
+        RangeError::InvalidSeconds(timestamp, max) => Some(format!(
+            "Invalid timestamp range: {} (seconds {} exceeds {}, did you mean {}?)",
+            range.text,
+            timestamp.seconds,
+            max,
+            suggested_time(*timestamp)
+        )),
+        RangeError::TooLarge(timestamp) => Some(format!(
+            "Invalid timestamp range: {} (time {} is too large)",
+            range.text,
+            timestamp.to_string_canonical()
+        )),
+        RangeError::InvalidSpeed(factor) => Some(format!(
+            "Invalid timestamp range: {} (speed factor {} must be between {} and {})",
+            range.text,
+            factor,
+            SPEED_FACTOR_RANGE.start(),
+            SPEED_FACTOR_RANGE.end()
+        )),
+        RangeError::InvalidMultiplier(count) => Some(format!(
+            "Invalid timestamp range: {} (multiplier x{} must be between {} and {})",
+            range.text,
+            count,
+            MULTIPLIER_RANGE.start(),
+            MULTIPLIER_RANGE.end()
+        )),
+        RangeError::MissingFrameRate => {
+            Some(format!("Invalid timestamp range: {} (frame counts need a frame rate)", range.text))
+        }
+        RangeError::ListOutOfOrder => Some(format!(
+            "Invalid timestamp range: {} (a part starts before the previous one ends)",
+            range.text
+        )),
+        RangeError::None => None,
+    }
+}
+
+/// A timestamp range located in the input, with its byte span and duration.
+/// Ranges that failed validation carry a zero duration and an error message.
+#[derive(Debug, Clone, Serialize)]
+pub struct RangeInfo {
+    pub start_pos: usize,
+    pub end_pos: usize,
+    pub text: String,
+    pub seconds: u32,
+    pub error: Option<String>,
+}
+
+/// Lists every range in the input without grouping or failing on invalid ranges.
+/// Only malformed timestamp-like text is an error, as in `calculate_durations`;
+/// under `skip_malformed` it is passed over and its messages returned instead.
+pub fn list_ranges(input: &str, options: &ParseOptions) -> Result<(Vec<RangeInfo>, Vec<String>), ScriptError> {
+    let (ranges, skipped) = find_all_ranges(input, options)?;
+    let ranges = ranges
+        .iter()
+        .map(|range| RangeInfo {
+            start_pos: range.start_pos,
+            end_pos: range.end_pos,
+            text: range.text.clone(),
+            seconds: range.duration,
+            error: range_error_message(range),
+        })
+        .collect();
+    Ok((ranges, skipped))
+}
+
+/// Sums the durations of already-listed ranges, saturating at `u32::MAX`;
+/// errored ranges count as zero.
+pub fn total_duration(ranges: &[RangeInfo]) -> DurationResult {
+    let seconds = ranges
+        .iter()
+        .filter(|range| range.error.is_none())
+        .fold(0u32, |sum, range| sum.saturating_add(range.seconds));
+    DurationResult::new(seconds)
+}
+
+pub fn format_duration(seconds: u32) -> String {
+    let mins = seconds / 60;
+    let secs = seconds % 60;
+    format!("{}:{:02}", mins, secs)
+}
+
+/// Formats a signed number of seconds like `format_duration`, with a leading
+/// `-` when negative (e.g. `-1:05`).
+pub fn format_signed_duration(seconds: i64) -> String {
+    let magnitude = seconds.unsigned_abs();
+    let sign = if seconds < 0 { "-" } else { "" };
+    format!("{}{}:{:02}", sign, magnitude / 60, magnitude % 60)
+}
+
+/// A signed difference between two durations.
+#[derive(Debug, Clone, Serialize)]
+pub struct DurationDelta {
+    pub seconds: i64,
+    pub format: String,
+}
+
+impl DurationDelta {
+    pub fn new(seconds: i64) -> Self {
+        DurationDelta {
+            seconds,
+            format: format_signed_duration(seconds),
+        }
+    }
+
+    /// The change from `before` to `after`.
+    pub fn between(before: u32, after: u32) -> Self {
+        DurationDelta::new(after as i64 - before as i64)
+    }
+}
+
+/// How far a total is from a target runtime: `remaining` is positive while
+/// time is left to fill and negative once over.
+#[derive(Debug, Clone, Serialize)]
+pub struct Budget {
+    pub total: DurationResult,
+    pub target: DurationResult,
+    pub remaining: DurationDelta,
+    pub message: String,
+}
+
+impl Budget {
+    pub fn new(total: DurationResult, target_secs: u32) -> Self {
+        let remaining = DurationDelta::between(total.seconds, target_secs);
+        // The gap between two u32 durations always fits back in a u32
+        let magnitude = format_duration(remaining.seconds.unsigned_abs() as u32);
+        let message = match remaining.seconds {
+            0 => "exactly on target".to_string(),
+            s if s > 0 => format!("need {} more", magnitude),
+            _ => format!("{} over", magnitude),
+        };
+        Budget {
+            total,
+            target: DurationResult::new(target_secs),
+            remaining,
+            message,
+        }
+    }
+}
+
+/// Rendering conventions for formatted strings. Only affects `format` fields,
+/// never the integer `seconds`. The default is plain ASCII with no grouping.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FormatLocale {
+    /// Thousands separator for the leading minutes, e.g. `,` gives `1,234:56`
+    pub grouping_separator: Option<char>,
+    /// Always render at least two minute digits, e.g. `00:05` instead of
+    /// `0:05`, for fixed-width columns.
+    pub pad_minutes: bool,
+    /// Decimal separator for fractional values, e.g. `,` gives `1,5` minutes
+    /// or `2,5 seconds`; a period when unset.
+    pub decimal_separator: Option<char>,
+}
+
+// Insert `separator` between groups of three digits, counting from the right
+fn group_digits(digits: &str, separator: char) -> String {
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(separator);
+        }
+        grouped.push(c);
+    }
+    grouped
+}
+
+/// `format_duration` following the given locale's conventions.
+pub fn format_duration_localized(seconds: u32, locale: &FormatLocale) -> String {
+    let mins = if locale.pad_minutes {
+        format!("{:02}", seconds / 60)
+    } else {
+        (seconds / 60).to_string()
+    };
+    let mins = match locale.grouping_separator {
+        Some(separator) => group_digits(&mins, separator),
+        None => mins,
+    };
+    format!("{}:{:02}", mins, seconds % 60)
+}
+
+/// `format_signed_duration` following the given locale's conventions, e.g.
+/// `-00:05` with `pad_minutes`.
+pub fn format_signed_duration_localized(seconds: i64, locale: &FormatLocale) -> String {
+    let magnitude = u32::try_from(seconds.unsigned_abs()).unwrap_or(u32::MAX);
+    let sign = if seconds < 0 { "-" } else { "" };
+    format!("{}{}", sign, format_duration_localized(magnitude, locale))
+}
+
+/// Renders `decimal_minutes` with the locale's decimal separator, e.g. `1,5`.
+pub fn format_decimal_minutes(seconds: u32, locale: &FormatLocale) -> String {
+    with_decimal_separator(decimal_minutes(seconds).to_string(), locale)
+}
+
+fn with_decimal_separator(number: String, locale: &FormatLocale) -> String {
+    match locale.decimal_separator {
+        Some(separator) if separator != '.' => number.replacen('.', &separator.to_string(), 1),
+        _ => number,
+    }
+}
+
+/// Renders seconds as an ISO 8601 duration, e.g. `PT1M30S` or `PT1H3M20S`.
+/// Zero components are omitted; zero itself is `PT0S`.
+pub fn format_duration_iso8601(seconds: u32) -> String {
+    if seconds == 0 {
+        return "PT0S".to_string();
+    }
+
+    let hours = seconds / 3600;
+    let mins = (seconds % 3600) / 60;
+    let secs = seconds % 60;
+    let mut iso = String::from("PT");
+    if hours > 0 {
+        iso.push_str(&format!("{}H", hours));
+    }
+    if mins > 0 {
+        iso.push_str(&format!("{}M", mins));
+    }
+    if secs > 0 {
+        iso.push_str(&format!("{}S", secs));
+    }
+    iso
+}
+
+/// Most fractional-second digits `format_duration_human` shows.
+pub const MAX_HUMAN_PRECISION: usize = 3;
+
+/// Renders a duration in milliseconds in words, e.g. "1 hour 2 minutes 5 seconds",
+/// for narration notes. Seconds show at most `precision` decimals (0 for whole
+/// seconds, capped at `MAX_HUMAN_PRECISION`) with trailing zeros dropped, after
+/// rounding half up, so 62 345 ms is "1 minute 2.3 seconds" at precision 1 and
+/// 59 960 ms is "1 minute". Units are singular only when the number shown is
+/// exactly 1, zero units are left out, and zero is "0 seconds".
+pub fn format_duration_human(millis: u64, precision: usize) -> String {
+    format_duration_human_localized(millis, precision, &FormatLocale::default())
+}
+
+/// `format_duration_human` in whole seconds, the usual form for narration
+/// notes: 1 950 ms is "2 seconds".
+pub fn format_duration_human_whole(millis: u64) -> String {
+    format_duration_human(millis, 0)
+}
+
+/// Same as `format_duration_human`, with the locale's decimal separator in
+/// fractional seconds, e.g. "2,5 seconds".
+pub fn format_duration_human_localized(millis: u64, precision: usize, locale: &FormatLocale) -> String {
+    let precision = precision.min(MAX_HUMAN_PRECISION);
+    let step = 10u64.pow((MAX_HUMAN_PRECISION - precision) as u32);
+    let rounded = millis.saturating_add(step / 2) / step * step;
+
+    let hours = rounded / 3_600_000;
+    let minutes = (rounded / 60_000) % 60;
+    let second_millis = rounded % 60_000;
+    let fraction = format!("{:03}", second_millis % 1000);
+    let fraction = fraction[..precision].trim_end_matches('0');
+    let seconds = if fraction.is_empty() {
+        (second_millis / 1000).to_string()
+    } else {
+        with_decimal_separator(format!("{}.{}", second_millis / 1000, fraction), locale)
+    };
+
+    let unit = |count: &str, name: &str| {
+        if count == "1" { format!("1 {}", name) } else { format!("{} {}s", count, name) }
+    };
+    let mut parts = Vec::new();
+    if hours > 0 {
+        parts.push(unit(&hours.to_string(), "hour"));
+    }
+    if minutes > 0 {
+        parts.push(unit(&minutes.to_string(), "minute"));
+    }
+    if second_millis > 0 || parts.is_empty() {
+        parts.push(unit(&seconds, "second"));
+    }
+    parts.join(" ")
+}
+
+/// Parses a duration as rendered by `format_duration`, so every output of it
+/// round-trips. Unlike range endpoints, `M:SS` minutes are unbounded here
+/// (totals over an hour render as e.g. `61:40`); `H:MM:SS` is also accepted.
+pub fn parse_duration(text: &str) -> Result<u32, String> {
+    let invalid = || format!("Invalid duration: {}", text);
+    let (_, timestamp) = all_consuming(parse_timestamp)
+        .parse(text.trim())
+        .map_err(|_| invalid())?;
+
+    let three_fields = text.matches(':').count() == 2;
+    if timestamp.seconds > 59 || (three_fields && timestamp.minutes > 59) {
+        return Err(invalid());
+    }
+
+    let seconds = timestamp.hours as u64 * 3600 + timestamp.minutes as u64 * 60 + timestamp.seconds as u64;
+    u32::try_from(seconds).map_err(|_| invalid())
+}
+
+// A unit word or abbreviation of `parse_human_duration`, as its length in seconds
+fn human_unit(input: &str) -> IResult<&str, u32> {
+    let (rest, word) = alpha1(input)?;
+    let seconds = match word.to_ascii_lowercase().as_str() {
+        "h" | "hr" | "hrs" | "hour" | "hours" => 3600,
+        "m" | "min" | "mins" | "minute" | "minutes" => 60,
+        "s" | "sec" | "secs" | "second" | "seconds" => 1,
+        _ => return Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Tag))),
+    };
+    Ok((rest, seconds))
+}
+
+/// Parses a spoken-style duration such as "1 minute 30 seconds", "1 min, 30 sec",
+/// "2 hours and 5 minutes" or "1h30m" into seconds. Units are hours, minutes
+/// and seconds, in singular, plural or abbreviated form (`h`/`hr`, `m`/`min`,
+/// `s`/`sec`), case-insensitive, each given at most once and in any order.
+/// Numbers are whole; parts may be separated by blanks, a comma or "and".
+pub fn parse_human_duration(text: &str) -> Result<u32, ParseError> {
+    let malformed = || ParseError::MalformedDuration(text.to_string());
+    let part = || (parse_number, space0, human_unit).map(|(count, _, unit)| (count, unit));
+    let separator = (space0, opt(tag(",")), space0, opt((tag_no_case("and"), space1)));
+    let (_, (first, rest)) = all_consuming((part(), many0(preceded(separator, part()))))
+        .parse(text.trim())
+        .map_err(|_| malformed())?;
+
+    let mut seen = Vec::new();
+    let mut total: u32 = 0;
+    for (count, unit) in std::iter::once(first).chain(rest) {
+        if seen.contains(&unit) {
+            return Err(malformed());
+        }
+        seen.push(unit);
+        total = count
+            .checked_mul(unit)
+            .and_then(|seconds| total.checked_add(seconds))
+            .ok_or_else(malformed)?;
+    }
+    Ok(total)
+}
+
+/// Cumulative points dividing `total_secs` into `parts` equal parts, rounded to
+/// the nearest second: `parts - 1` points, none for 0 or 1 part. When there are
+/// more parts than seconds, neighbouring points may coincide.
+pub fn split_points(total_secs: u32, parts: u32) -> Vec<DurationResult> {
+    let parts = parts as u64;
+    (1..parts)
+        .map(|k| {
+            let point = (total_secs as u64 * k * 2 + parts) / (parts * 2);
+            DurationResult::new(point as u32)
+        })
+        .collect()
+}
+
+/// A stretch of the timeline covered without a gap by one or more ranges.
+#[derive(Debug, Clone, Serialize)]
+pub struct CoveredSpan {
+    pub start: DurationResult,
+    pub end: DurationResult,
+    pub length: DurationResult,
+    // Number of ranges merged into the span, counting each part of a range list
+    pub ranges: usize,
+}
+
+/// The timeline covered by the script's ranges, with overlapping or touching
+/// ranges merged, in start order. Bare durations have no position and are ignored.
+/// Also returns the messages of malformed spans passed over under `skip_malformed`.
+pub fn covered_spans(input: &str, options: &ParseOptions) -> Result<(Vec<CoveredSpan>, Vec<String>), ScriptError> {
+    let (ranges, skipped) = find_all_ranges(input, options)?;
+    if let Some(error) = ranges.iter().find_map(range_error) {
+        return Err(error);
+    }
+
+    // Each part of a range list is its own interval, so the gaps between parts
+    // are not covered
+    let mut intervals: Vec<(u32, u32)> = ranges
+        .iter()
+        .flat_map(|range| match range.parts.as_slice() {
+            [] => range_span(range).into_iter().collect(),
+            parts => parts.to_vec(),
+        })
+        .collect();
+    intervals.sort_unstable();
+
+    let mut merged: Vec<(u32, u32, usize)> = Vec::new();
+    for (start, end) in intervals {
+        match merged.last_mut() {
+            Some((_, last_end, count)) if start <= *last_end => {
+                *last_end = (*last_end).max(end);
+                *count += 1;
+            }
+            _ => merged.push((start, end, 1)),
+        }
+    }
+
+    let spans = merged
+        .into_iter()
+        .map(|(start, end, ranges)| CoveredSpan {
+            start: DurationResult::new(start),
+            end: DurationResult::new(end),
+            length: DurationResult::new(end.saturating_sub(start)),
+            ranges,
+        })
+        .collect();
+    Ok((spans, skipped))
+}
+
+/// The first pair of consecutive ranges whose start time goes back, with the
+/// ids of their lines as numbered by `calculate_durations`.
+#[derive(Debug, Clone, Serialize)]
+pub struct OrderViolation {
+    pub previous_line: usize,
+    pub previous_range: String,
+    pub previous_start: DurationResult,
+    pub line: usize,
+    pub range: String,
+    pub start: DurationResult,
+}
+
+/// Whether the start times of a script's ranges never decrease.
+#[derive(Debug, Clone, Serialize)]
+pub struct OrderCheck {
+    pub ordered: bool,
+    // Number of ranges checked
+    pub ranges: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub violation: Option<OrderViolation>,
+    // Malformed text passed over under `skip_malformed`, one message each
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warnings: Option<Vec<String>>,
+}
+
+/// Checks that the ranges start in chronological order, each at or after the
+/// start of the one before, and reports the first pair that does not. Only
+/// start times are compared, so overlapping ranges can still be in order.
+/// Bare durations have no position and are skipped; every line is numbered,
+/// as `min_duration_secs` is not applied.
+pub fn check_order(input: &str, options: &ParseOptions) -> Result<OrderCheck, ScriptError> {
+    let (ranges, skipped) = find_all_ranges(input, options)?;
+    let warnings = (!skipped.is_empty()).then_some(skipped);
+    if let Some(error) = ranges.iter().find_map(range_error) {
+        return Err(error);
+    }
+
+    let mut line = 0;
+    let mut checked = 0;
+    let mut previous: Option<(usize, &ParsedRange)> = None;
+    for (i, range) in ranges.iter().enumerate() {
+        if i == 0 || !joins_previous(input, &ranges[i - 1], range, options) {
+            line += 1;
+        }
+        if range.bare {
+            continue;
+        }
+        checked += 1;
+        if let Some((previous_line, previous_range)) = previous
+            && range.start.to_seconds() < previous_range.start.to_seconds()
+        {
+            return Ok(OrderCheck {
+                ordered: false,
+                ranges: checked,
+                violation: Some(OrderViolation {
+                    previous_line,
+                    previous_range: previous_range.text.clone(),
+                    previous_start: DurationResult::new(previous_range.start.to_seconds()),
+                    line,
+                    range: range.text.clone(),
+                    start: DurationResult::new(range.start.to_seconds()),
+                }),
+                warnings,
+            });
+        }
+        previous = Some((line, range));
+    }
+
+    Ok(OrderCheck {
+        ordered: true,
+        ranges: checked,
+        violation: None,
+        warnings,
+    })
+}
+
+/// The longest of `covered_spans`, the earliest one on a tie; `None` when the
+/// script has no ranges. Skipped messages are passed on as by `covered_spans`.
+pub fn longest_span(input: &str, options: &ParseOptions) -> Result<(Option<CoveredSpan>, Vec<String>), ScriptError> {
+    let (spans, skipped) = covered_spans(input, options)?;
+    let longest = spans
+        .into_iter()
+        .rev()
+        .max_by_key(|span| span.length.seconds);
+    Ok((longest, skipped))
+}
+
+/// Why a single timestamp was rejected by `parse_timestamp_public`, or a
+/// phrase by `parse_human_duration`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// Not `H:MM:SS` or `M:SS`, or followed by other text
+    Malformed(String),
+    InvalidMinutes(u32),
+    InvalidSeconds(u32),
+    /// Not a phrase of numbers and units, a unit given twice, or too long
+    MalformedDuration(String),
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::Malformed(text) => write!(f, "Malformed timestamp: {}", text),
+            ParseError::InvalidMinutes(mins) => {
+                write!(f, "Invalid timestamp: minutes {} exceeds 59, use H:MM:SS format", mins)
+            }
+            ParseError::InvalidSeconds(secs) => write!(f, "Invalid timestamp: seconds {} exceeds 59", secs),
+            ParseError::MalformedDuration(text) => write!(f, "Malformed duration: {}", text),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl ParseError {
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            ParseError::Malformed(_) | ParseError::MalformedDuration(_) => ErrorCategory::Malformed,
+            ParseError::InvalidMinutes(_) => ErrorCategory::InvalidMinutes,
+            ParseError::InvalidSeconds(_) => ErrorCategory::InvalidSeconds,
+        }
+    }
+}
+
+/// Parses one timestamp (`H:MM:SS` or `M:SS`), rejecting any trailing text.
+/// Only the syntax is checked; call `Timestamp::validate` for the 59 caps.
+pub fn parse_timestamp_public(text: &str) -> Result<Timestamp, ParseError> {
+    all_consuming(parse_timestamp)
+        .parse(text.trim())
+        .map(|(_, timestamp)| timestamp)
+        .map_err(|_| ParseError::Malformed(text.to_string()))
+}
+
+/// Whether `text` is a timestamp that would be accepted as a range endpoint.
+pub fn is_valid_timestamp(text: &str) -> bool {
+    parse_timestamp_public(text).is_ok_and(|timestamp| timestamp.validate().is_ok())
+}
+
+/// A script scanned once for ranges, so durations and cleaning can reuse the scan.
+pub struct ParsedScript {
+    input: String,
+    ranges: Result<Vec<ParsedRange>, ScriptError>,
+    malformed: Vec<Range<usize>>,
+    adjustments: Vec<Adjustment>,
+    escapes: Vec<usize>,
+    skipped: Vec<String>,
+    options: ParseOptions,
+}
+
+impl ParsedScript {
+    /// Scan-time options (such as `bare_durations`) are fixed here; grouping
+    /// options are taken per call.
+    pub fn parse(input: &str, options: &ParseOptions) -> Self {
+        let mut scan = scan_script(input, options);
+        let malformed = scan.malformed.iter().map(|span| span.start_pos..span.end_pos).collect();
+        let adjustments = std::mem::take(&mut scan.adjustments);
+        let escapes = std::mem::take(&mut scan.escapes);
+        let skipped = scan.take_skipped(options);
+        ParsedScript {
+            input: input.to_string(),
+            ranges: scan.into_ranges(),
+            malformed,
+            adjustments,
+            escapes,
+            skipped,
+            options: options.clone(),
+        }
+    }
+
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    /// The options the script was scanned with.
+    pub fn options(&self) -> &ParseOptions {
+        &self.options
+    }
+
+    pub fn range_count(&self) -> usize {
+        self.ranges.as_ref().map_or(0, Vec::len)
+    }
+
+    /// Same as `calculate_durations` on the original input.
+    pub fn durations(&self, options: &ParseOptions) -> Result<ParseOutput, ScriptError> {
+        let ranges = self.ranges.as_ref().map_err(Clone::clone)?;
+        let mut output = durations_from_ranges(&self.input, ranges, &self.adjustments, options)?;
+        output.warnings = (!self.skipped.is_empty()).then(|| self.skipped.clone());
+        Ok(output)
+    }
+
+    /// The script with every range rewritten in canonical form, `(M:SS-M:SS)`
+    /// or `(H:MM:SS-H:MM:SS)` with a plain hyphen, and every bare duration as
+    /// `M:SS` or `H:MM:SS`. Everything else is kept as written, including a speed
+    /// factor or multiplier after a range, range lists, frame ranges and
+    /// invalid ranges.
+    pub fn normalize(&self) -> Result<String, ScriptError> {
+        let ranges = self.ranges.as_ref().map_err(Clone::clone)?;
+        let mut normalized = String::with_capacity(self.input.len());
+        let mut pos = 0;
+        for range in ranges {
+            let Some((canonical, len)) = canonical_range(range, &self.options) else {
+                continue;
+            };
+            normalized.push_str(&self.input[pos..range.start_pos]);
+            normalized.push_str(&canonical);
+            pos = range.start_pos + len;
+        }
+        normalized.push_str(&self.input[pos..]);
+        Ok(normalized)
+    }
+
+    /// Same as `clean_script` on the original input.
+    pub fn clean(&self) -> String {
+        self.clean_segments().collect()
+    }
+
+    /// Same as `clean_preview` on the original input.
+    pub fn clean_preview(&self, max_removals: usize) -> CleanPreview {
+        let (ranges, adjustments) = self.removals();
+        preview_from_ranges(&self.input, ranges, adjustments, &self.escapes, &self.options, max_removals)
+    }
+
+    /// Same as `clean_edits` on the original input.
+    pub fn clean_edits(&self) -> Vec<TextEdit> {
+        let (ranges, adjustments) = self.removals();
+        edits_from_pieces(&self.input, CleanPieces::new(&self.input, ranges, adjustments, &self.escapes, &self.options))
+    }
+
+    /// Same as `clean_spans` on the original input.
+    pub fn clean_spans(&self) -> Vec<RemovedSpan> {
+        let (ranges, adjustments) = self.removals();
+        spans_from_pieces(&self.input, CleanPieces::new(&self.input, ranges, adjustments, &self.escapes, &self.options))
+    }
+
+    /// Where in the original text each removal of `clean_malformed` lies.
+    pub fn clean_malformed_spans(&self) -> Vec<RemovedSpan> {
+        spans_from_pieces(&self.input, CleanPieces::malformed(&self.input, self.malformed.clone()))
+    }
+
+    /// Same as `clean_malformed` on the original input.
+    pub fn clean_malformed(&self) -> String {
+        self.clean_malformed_segments().collect()
+    }
+
+    /// Same as `clean_malformed_edits` on the original input.
+    pub fn clean_malformed_edits(&self) -> Vec<TextEdit> {
+        edits_from_pieces(&self.input, CleanPieces::malformed(&self.input, self.malformed.clone()))
+    }
+
+    /// The text of `clean_malformed()` in chunks.
+    pub fn clean_malformed_segments(&self) -> CleanSegments<'_> {
+        CleanSegments {
+            pieces: CleanPieces::malformed(&self.input, self.malformed.clone()),
+        }
+    }
+
+    /// The cleaned text in chunks; concatenated, they equal `clean()`.
+    pub fn clean_segments(&self) -> CleanSegments<'_> {
+        let (ranges, adjustments) = self.removals();
+        CleanSegments::new(&self.input, ranges, adjustments, &self.escapes, &self.options)
+    }
+
+    // What cleaning removes. If parsing fails, nothing is: the input is
+    // returned as-is (minus the escapes of literal ranges)
+    fn removals(&self) -> (&[ParsedRange], &[Adjustment]) {
+        match &self.ranges {
+            Ok(ranges) => (ranges, &self.adjustments),
+            Err(_) => (&[], &[]),
+        }
+    }
+}
+
+// The canonical form of a range and the length of the text it replaces: the
+// bracket of a plain range (not what follows it) or a whole bare duration
+fn canonical_range(range: &ParsedRange, options: &ParseOptions) -> Option<(String, usize)> {
+    if !matches!(range.error, RangeError::None) {
+        return None;
+    }
+    if range.bare {
+        return Some((range.start.to_string_canonical(), range.text.len()));
+    }
+
+    // Parse the bracket again, with any Unicode digits mapped as in the scan
+    let digits = if options.unicode_digits { normalize_digits(&range.text) } else { None };
+    let (text, offsets) = match &digits {
+        Some((text, offsets)) => (text.as_str(), Some(offsets)),
+        None => (range.text.as_str(), None),
+    };
+    let (rest, result) = parse_range(text, options)
+        .or_else(|e| if options.interval_notation { parse_interval(text, options) } else { Err(e) })
+        .ok()?;
+    if !matches!(result.error, RangeError::None) {
+        return None;
+    }
+    let len = text.len() - rest.len();
+    let canonical = format!(
+        "({}-{})",
+        result.start.to_string_canonical(),
+        result.end.to_string_canonical()
+    );
+    Some((canonical, offsets.map_or(len, |offsets| offsets[len])))
+}
+
+/// Groups the ranges found in `input` into lines and totals them.
+///
+/// Input without any ranges is not an error: it yields `Ok` with no lines and a
+/// zero total. Only malformed or invalid ranges produce `Err`; callers that need
+/// at least one line must check `lines.is_empty()` themselves.
+pub fn calculate_durations(input: &str, options: &ParseOptions) -> Result<ParseOutput, ScriptError> {
+    let mut scan = scan_script(input, options);
+    let adjustments = std::mem::take(&mut scan.adjustments);
+    let skipped = scan.take_skipped(options);
+    let ranges = scan.into_ranges()?;
+    let mut output = durations_from_ranges(input, &ranges, &adjustments, options)?;
+    output.warnings = (!skipped.is_empty()).then_some(skipped);
+    Ok(output)
+}
+
+/// The total and line count of `calculate_durations`, without building the
+/// per-line results, and the messages it would list as `warnings`. Errors are
+/// the same; sections do not change the total.
+pub fn calculate_total(
+    input: &str,
+    options: &ParseOptions,
+) -> Result<(DurationResult, usize, Vec<String>), ScriptError> {
+    let (ranges, skipped) = find_all_ranges(input, options)?;
+    if let Some(error) = ranges.iter().find_map(range_error) {
+        return Err(error);
+    }
+
+    let mut total = 0u32;
+    let mut lines = 0;
+    // Ids count every line, as in `calculate_durations`, filtered ones included
+    let mut id = 0;
+    let mut i = 0;
+    while i < ranges.len() {
+        let first = i;
+        let mut group_duration = 0u32;
+        id += 1;
+        loop {
+            if options.max_duration_severity == Severity::Error
+                && let Some(message) = check_max(&ranges[i], options)
+            {
+                return Err(ScriptError::new(ErrorCategory::TooLong, message));
+            }
+            if options.zero_length == Some(Severity::Error) && is_zero_length(&ranges[i]) {
+                return Err(zero_length_error(&ranges[i], id));
+            }
+            group_duration = group_duration.saturating_add(ranges[i].duration);
+            if i + 1 < ranges.len() && joins_previous(input, &ranges[i], &ranges[i + 1], options) {
+                i += 1;
+            } else {
+                break;
+            }
+        }
+        i += 1;
+
+        if options.min_duration_secs.is_none_or(|min| group_duration >= min) {
+            total = total.saturating_add(group_duration);
+            lines += 1;
+            if let Some(max) = options.max_total_secs.filter(|&max| total > max) {
+                let texts: Vec<&str> = ranges[first..i].iter().map(|range| range.text.as_str()).collect();
+                return Err(total_cap_error(total, max, id, &texts.join(" + ")));
+            }
+        }
+    }
+
+    Ok((DurationResult::new(total), lines, skipped))
+}
+
+/// Like `calculate_durations` for a script the caller has already split into lines.
+///
+/// Each element is parsed on its own, so `+` never joins ranges across elements.
+/// Line ids count from 1 across all elements, as in `calculate_durations`.
+/// `section_prefix` does not apply; errors name the offending element,
+/// counting from 1.
+pub fn calculate_line_durations<S: AsRef<str>>(
+    lines: &[S],
+    options: &ParseOptions,
+) -> Result<ParseOutput, ScriptError> {
+    let mut results = Vec::new();
+    let mut total = 0;
+    let mut filtered = 0;
+    let mut skipped = Vec::new();
+    let mut id = 1;
+
+    for (index, line) in lines.iter().enumerate() {
+        let line = line.as_ref();
+        let prefix = format!("Line {}: ", index + 1);
+        let with_index = |e: ScriptError| e.prefixed(&prefix);
+        let mut scan = scan_script(line, options);
+        skipped.extend(scan.take_skipped(options).into_iter().map(|message| format!("{}{}", prefix, message)));
+        let ranges = scan.into_ranges().map_err(with_index)?;
+        if let Some(error) = ranges.iter().find_map(range_error) {
+            return Err(with_index(error));
+        }
+
+        let group = group_lines(line, &ranges, options, &mut id, total).map_err(with_index)?;
+        total = total.saturating_add(group.total);
+        filtered += group.filtered;
+        results.extend(group.lines);
+    }
+
+    let mut output = ParseOutput {
+        lines: results,
+        total: DurationResult::new(total),
+        filtered: options.min_duration_secs.map(|_| filtered),
+        sections: None,
+        remaining: None,
+        rounding_warning: None,
+        totals: None,
+        total_lines: None,
+        adjustments: None,
+        histogram: None,
+        stats: None,
+        warnings: (!skipped.is_empty()).then_some(skipped),
+        rounded_total: None,
+    };
+    output.note_rounding();
+    Ok(output)
+}
+
+fn durations_from_ranges(
+    input: &str,
+    ranges: &[ParsedRange],
+    adjustments: &[Adjustment],
+    options: &ParseOptions,
+) -> Result<ParseOutput, ScriptError> {
+    // Check for invalid ranges
+    if let Some(error) = ranges.iter().find_map(range_error) {
+        return Err(error);
+    }
+
+    let mut id = 1;
+
+    if let Some(prefix) = options.section_prefix.as_deref().filter(|p| !p.is_empty()) {
+        let mut sections = Vec::new();
+        let mut grand_total = 0;
+        let mut filtered = 0;
+        let mut first = 0;
+        for (label, span) in find_sections(input, prefix) {
+            let count = ranges[first..]
+                .iter()
+                .take_while(|range| range.start_pos < span.end)
+                .count();
+            let section_ranges = &ranges[first..first + count];
+            first += count;
+
+            // Skip a preamble without any ranges before the first header
+            if label.is_none() && section_ranges.is_empty() {
+                continue;
+            }
+
+            let group = group_lines(input, section_ranges, options, &mut id, grand_total)?;
+            grand_total = grand_total.saturating_add(group.total);
+            filtered += group.filtered;
+            sections.push(SectionResult {
+                label,
+                lines: group.lines,
+                subtotal: DurationResult::new(group.total),
+            });
+        }
+
+        let mut output = ParseOutput {
+            lines: Vec::new(),
+            total: DurationResult::new(grand_total),
+            filtered: options.min_duration_secs.map(|_| filtered),
+            sections: Some(sections),
+            remaining: None,
+            rounding_warning: None,
+            totals: None,
+            total_lines: None,
+            adjustments: options.nudges.then(|| adjustments.to_vec()),
+            histogram: None,
+            stats: None,
+            warnings: None,
+            rounded_total: None,
+        };
+        output.note_rounding();
+        return Ok(output);
+    }
+
+    let group = group_lines(input, ranges, options, &mut id, 0)?;
+    let mut output = ParseOutput {
+        lines: group.lines,
+        total: DurationResult::new(group.total),
+        filtered: options.min_duration_secs.map(|_| group.filtered),
+        sections: None,
+        remaining: None,
+        rounding_warning: None,
+        totals: None,
+        total_lines: None,
+        adjustments: options.nudges.then(|| adjustments.to_vec()),
+        histogram: None,
+        stats: None,
+        warnings: None,
+        rounded_total: None,
+    };
+    output.note_rounding();
+    Ok(output)
+}
+
+// Split the input at header lines starting with `prefix`. Each section is its
+// header label (None for text before the first header) and its byte span.
+fn find_sections(input: &str, prefix: &str) -> Vec<(Option<String>, Range<usize>)> {
+    let mut sections = Vec::new();
+    let mut label = None;
+    let mut start = 0;
+    let mut pos = 0;
+
+    for line in input.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with(prefix) {
+            sections.push((label, start..pos));
+            label = Some(trimmed.trim_start_matches(prefix).trim().to_string());
+            start = pos;
+        }
+        pos += line.len();
+    }
+    sections.push((label, start..input.len()));
+    sections
+}
+
+// Whether `next` is chained onto `previous` with " + " (or a word connector)
+// into one line
+pub(crate) fn joins_previous(
+    input: &str,
+    previous: &ParsedRange,
+    next: &ParsedRange,
+    options: &ParseOptions,
+) -> bool {
+    !options.ungroup && connectors(options).contains(&input[previous.end_pos..next.start_pos].trim())
+}
+
+// Byte span of a connector just before `pos`, from the connector up to `pos`,
+// when only blanks lie between them and the connector stands alone (preceded
+// by whitespace or nothing), so "C++" or "band" is never taken for one
+fn connector_before(input: &str, pos: usize, connectors: &[&str]) -> Option<Range<usize>> {
+    let trimmed = input[..pos].trim_end_matches([' ', '\t']);
+    connectors.iter().find_map(|connector| {
+        let head = trimmed.strip_suffix(connector)?;
+        head.chars().next_back().is_none_or(char::is_whitespace).then_some(head.len()..pos)
+    })
+}
+
+// Byte span of a standalone connector just after `pos`, from `pos` to past the connector
+fn connector_after(input: &str, pos: usize, connectors: &[&str]) -> Option<Range<usize>> {
+    let trimmed = input[pos..].trim_start_matches([' ', '\t']);
+    connectors.iter().find_map(|connector| {
+        let rest = trimmed.strip_prefix(connector)?;
+        rest.chars().next().is_none_or(char::is_whitespace).then_some(pos..input.len() - rest.len())
+    })
+}
+
+// Flags a connector with nothing to join on one side of a line's ranges, as in
+// "(0:00-1:00) +" or "+ (0:00-1:00)": only blanks up to the line boundary
+fn orphan_connector_warnings(input: &str, group: &[ParsedRange], options: &ParseOptions) -> Vec<String> {
+    let (Some(first), Some(last)) = (group.first(), group.last()) else {
+        return Vec::new();
+    };
+    let mut warnings = Vec::new();
+    if let Some(connector) = connector_before(input, first.start_pos, connectors(options))
+        && input[..connector.start].rsplit('\n').next().is_some_and(|head| head.trim().is_empty())
+    {
+        let text = input[connector].trim();
+        warnings.push(format!("Connector {} before {} has nothing to join", text, first.text));
+    }
+    if let Some(connector) = connector_after(input, last.end_pos, connectors(options))
+        && input[connector.end..].split('\n').next().is_some_and(|tail| tail.trim().is_empty())
+    {
+        let text = input[connector].trim();
+        warnings.push(format!("Connector {} after {} has nothing to join", text, last.text));
+    }
+    warnings
+}
+
+// The text of the input line holding `range`, with every range removed and
+// trimmed; empty for a line of nothing but ranges
+pub(crate) fn line_label(input: &str, range: &ParsedRange, options: &ParseOptions) -> String {
+    let line_start = input[..range.start_pos].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = input[range.end_pos..].find('\n').map_or(input.len(), |i| range.end_pos + i);
+    clean_script(&input[line_start..line_end], options).trim().to_string()
+}
+
+// Start and end of a range in seconds from zero; bare durations have no position
+fn range_span(range: &ParsedRange) -> Option<(u32, u32)> {
+    Some((range.start.to_seconds(), range.end?.to_seconds()))
+}
+
+// Ranges over the maximum are reported per range, with the overage
+fn check_max(range: &ParsedRange, options: &ParseOptions) -> Option<String> {
+    let max = options.max_duration_secs?;
+    (range.duration > max).then(|| {
+        format!(
+            "Range {} is {} over the {} maximum",
+            range.text,
+            format_duration(range.duration - max),
+            format_duration(max)
+        )
+    })
+}
+
+// Flags the M:SS times of a range that `warn_large_minutes` let overflow
+fn large_minutes_warning(range: &ParsedRange, options: &ParseOptions) -> Option<String> {
+    if !options.warn_large_minutes {
+        return None;
+    }
+    let (max_minutes, _) = options.bounds.limits();
+    let large = [Some(range.start), range.end]
+        .into_iter()
+        .flatten()
+        .find(|timestamp| timestamp.minutes > max_minutes)?;
+    Some(format!(
+        "Range {} has {} minutes, counted as {}",
+        range.text,
+        large.minutes,
+        suggested_time(large)
+    ))
+}
+
+// Flags the times of a range whose seconds `overflow_seconds` carried over
+fn overflow_seconds_warning(range: &ParsedRange, options: &ParseOptions) -> Option<String> {
+    if !options.overflow_seconds {
+        return None;
+    }
+    let (_, max_seconds) = options.bounds.limits();
+    let overflowing = [Some(range.start), range.end]
+        .into_iter()
+        .flatten()
+        .find(|timestamp| timestamp.seconds > max_seconds)?;
+    Some(format!(
+        "Range {} has {} seconds, counted as {}",
+        range.text,
+        overflowing.seconds,
+        suggested_time(overflowing)
+    ))
 }
 
-fn format_duration(seconds: u32) -> String {
-    let mins = seconds / 60;
-    let secs = seconds % 60;
-    format!("{}:{:02}", mins, secs)
+// The error once the running total passes `max_total_secs`, naming the line
+// that pushed it over
+fn total_cap_error(total: u32, max: u32, id: usize, line: &str) -> ScriptError {
+    let message = format!(
+        "Total exceeds the {} maximum at line {}: {} brings it to {}",
+        format_duration(max),
+        id,
+        line,
+        format_duration(total)
+    );
+    ScriptError::new(ErrorCategory::TotalTooLong, message)
 }
 
-pub fn calculate_durations(input: &str) -> Result<ParseOutput, String> {
-    let ranges = find_all_ranges(input)?;
-    
-    // Check for invalid ranges
-    for range in &ranges {
-        match &range.error {
-            RangeError::EndBeforeStart => {
-                return Err(format!("Invalid timestamp range: {} (end time is before start time)", range.text));
-            }
-            RangeError::InvalidMinutes(mins) => {
-                return Err(format!("Invalid timestamp range: {} (minutes {} exceeds 59, use H:MM:SS format)", range.text, mins));
-            }
-            RangeError::InvalidSeconds(secs) => {
-                return Err(format!("Invalid timestamp range: {} (seconds {} exceeds 59)", range.text, secs));
-            }
-            RangeError::None => {}
-        }
-    }
-    
+fn is_zero_length(range: &ParsedRange) -> bool {
+    range.end == Some(range.start)
+}
+
+// Errors name the line by id; warnings are attached to the line itself
+fn zero_length_error(range: &ParsedRange, id: usize) -> ScriptError {
+    let message = format!("Range {} on line {} has zero length", range.text, id);
+    ScriptError::new(ErrorCategory::ZeroLength, message)
+}
+
+// Lines produced from a run of ranges, with their total and filtered count
+struct LineGroups {
+    lines: Vec<LineResult>,
+    total: u32,
+    filtered: usize,
+}
+
+fn group_lines(
+    input: &str,
+    ranges: &[ParsedRange],
+    options: &ParseOptions,
+    id: &mut usize,
+    prior_total: u32,
+) -> Result<LineGroups, ScriptError> {
     let mut lines = Vec::new();
-    let mut grand_total = 0;
-    let mut id = 1;
+    let mut grand_total = 0u32;
+    let mut filtered = 0;
+
+    let check_range = |range: &ParsedRange| {
+        let large_minutes = large_minutes_warning(range, options);
+        let overflow = overflow_seconds_warning(range, options);
+        check_max(range, options).into_iter().chain(large_minutes).chain(overflow)
+    };
 
     let mut i = 0;
     while i < ranges.len() {
         let mut group_texts = vec![ranges[i].text.clone()];
         let mut group_duration = ranges[i].duration;
-        let mut last_end = ranges[i].end_pos;
+        let mut warnings: Vec<String> = check_range(&ranges[i]).collect();
+        let mut expression = options.expressions.then(|| Expression::operand(&ranges[i]));
+        let mut span = range_span(&ranges[i]);
 
         // Check for consecutive ranges connected by " + "
         while i + 1 < ranges.len() {
-            let between = &input[last_end..ranges[i + 1].start_pos];
-            if between.trim() == "+" {
+            if joins_previous(input, &ranges[i], &ranges[i + 1], options) {
                 i += 1;
                 group_texts.push(ranges[i].text.clone());
-                group_duration += ranges[i].duration;
-                last_end = ranges[i].end_pos;
+                group_duration = group_duration.saturating_add(ranges[i].duration);
+                warnings.extend(check_range(&ranges[i]));
+                expression = expression
+                    .map(|left| Expression::binary(Operator::Add, left, Expression::operand(&ranges[i])));
+                if let Some((start, end)) = range_span(&ranges[i]) {
+                    span = Some((span.map_or(start, |(first, _)| first), end));
+                }
             } else {
                 break;
             }
         }
 
+        let group = &ranges[i + 1 - group_texts.len()..=i];
+        if options.max_duration_severity == Severity::Error
+            && let Some(message) = group.iter().find_map(|range| check_max(range, options))
+        {
+            return Err(ScriptError::new(ErrorCategory::TooLong, message));
+        }
+
+        if let Some(severity) = options.zero_length {
+            for range in group.iter().filter(|range| is_zero_length(range)) {
+                if severity == Severity::Error {
+                    return Err(zero_length_error(range, *id));
+                }
+                warnings.push(format!("Range {} has zero length", range.text));
+            }
+        }
+
+        warnings.extend(orphan_connector_warnings(input, group, options));
+
+        // Filter whole groups, after the chain has been resolved. A filtered
+        // group keeps its id, so ids and errors name the same line either way
+        if options.min_duration_secs.is_some_and(|min| group_duration < min) {
+            filtered += 1;
+            *id += 1;
+            i += 1;
+            continue;
+        }
+
         let input_text = group_texts.join(" + ");
         lines.push(LineResult {
-            id,
-            input: input_text,
-            result: DurationResult {
-                seconds: group_duration,
-                format: format_duration(group_duration),
-            },
+            id: *id,
+            input: input_text.clone(),
+            result: DurationResult::new(group_duration),
+            warnings,
+            // A single range is its own expression; only groups carry a tree
+            expression: expression.filter(|tree| matches!(tree, Expression::Binary { .. })),
+            span,
+            start: None,
+            end: None,
+            order: None,
+            target_delta: None,
+            bar: None,
         });
-        grand_total += group_duration;
-        id += 1;
+        grand_total = grand_total.saturating_add(group_duration);
+        let running_total = prior_total.saturating_add(grand_total);
+        if let Some(max) = options.max_total_secs.filter(|&max| running_total > max) {
+            return Err(total_cap_error(running_total, max, *id, &input_text));
+        }
+        *id += 1;
         i += 1;
     }
 
-    Ok(ParseOutput {
+    Ok(LineGroups {
         lines,
-        total: DurationResult {
-            seconds: grand_total,
-            format: format_duration(grand_total),
-        },
+        total: grand_total,
+        filtered,
     })
 }
 
-pub fn clean_script(input: &str) -> String {
-    let ranges = find_all_ranges(input);
-    
-    // If parsing fails or no ranges found, return original
-    let ranges = match ranges {
-        Ok(r) if !r.is_empty() => r,
-        _ => return input.to_string(),
-    };
-    
-    let mut result = String::with_capacity(input.len());
-    let mut last_pos = 0;
-    let mut i = 0;
-    
-    while i < ranges.len() {
-        let range = &ranges[i];
-        let text_start = last_pos;
-        let mut text_end = range.start_pos;
-        let mut skip_to = range.end_pos;
+pub fn clean_script(input: &str, options: &ParseOptions) -> String {
+    let (ranges, adjustments, escapes) = scan_removals(input, options);
+    CleanSegments::new(input, &ranges, &adjustments, &escapes, options).collect()
+}
+
+// The ranges and adjustments cleaning removes. If parsing fails or no ranges
+// are found, nothing is: the original is returned (minus the escapes of
+// literal ranges)
+fn scan_removals(input: &str, options: &ParseOptions) -> (Vec<ParsedRange>, Vec<Adjustment>, Vec<usize>) {
+    let mut scan = scan_script(input, options);
+    let adjustments = std::mem::take(&mut scan.adjustments);
+    let escapes = std::mem::take(&mut scan.escapes);
+    scan.take_skipped(options);
+    match scan.into_ranges() {
+        Ok(ranges) => (ranges, adjustments, escapes),
+        Err(_) => (Vec::new(), Vec::new(), escapes),
+    }
+}
+
+// One step of cleaning: text kept from the input (after connector cleanup),
+// followed by a removed span that is replaced by a joining space or nothing
+struct CleanPiece {
+    kept: Range<usize>,
+    kept_text: String,
+    removed: Range<usize>,
+    joiner: bool,
+}
+
+// Walks the removed spans of a script, producing the pieces of the cleaned text
+struct CleanPieces<'a> {
+    input: &'a str,
+    spans: Vec<Range<usize>>,
+    // Backslashes escaping literal ranges, dropped from kept text when tidying
+    escapes: Vec<usize>,
+    // Whether kept text gets unescaping and standalone connectors removed
+    tidy: bool,
+    // The connectors joining ranges, removed along with them
+    connectors: &'static [&'static str],
+    i: usize,
+    last_pos: usize,
+    // Last character written so far, for ranges with nothing kept between them
+    tail: Option<char>,
+    done: bool,
+    // The input without its leading and trailing whitespace, which is never touched
+    body: Range<usize>,
+}
+
+impl<'a> CleanPieces<'a> {
+    // Removes every range and tidies the text around them
+    fn new(
+        input: &'a str,
+        ranges: &[ParsedRange],
+        adjustments: &[Adjustment],
+        escapes: &[usize],
+        options: &ParseOptions,
+    ) -> Self {
+        let mut spans: Vec<Range<usize>> = ranges.iter().map(|range| range.start_pos..range.end_pos).collect();
+        if !adjustments.is_empty() {
+            spans.extend(adjustments.iter().map(|adjustment| adjustment.span.clone()));
+            spans.sort_by_key(|span| span.start);
+        }
+        Self::with_spans(input, spans, escapes.to_vec(), true, connectors(options))
+    }
+
+    // Removes only malformed spans, leaving everything else as written
+    fn malformed(input: &'a str, spans: Vec<Range<usize>>) -> Self {
+        Self::with_spans(input, spans, Vec::new(), false, RANGE_CONNECTORS)
+    }
+
+    fn with_spans(
+        input: &'a str,
+        spans: Vec<Range<usize>>,
+        escapes: Vec<usize>,
+        tidy: bool,
+        connectors: &'static [&'static str],
+    ) -> Self {
+        let body_start = input.len() - input.trim_start().len();
+        CleanPieces {
+            input,
+            spans,
+            escapes,
+            tidy,
+            connectors,
+            i: 0,
+            last_pos: 0,
+            tail: None,
+            done: false,
+            body: body_start..input.trim_end().len().max(body_start),
+        }
+    }
+
+    // Unescaping for kept text, copying any part that lies in the document's
+    // outer whitespace verbatim
+    fn kept_text(&self, kept: Range<usize>) -> String {
+        let inner = kept.start.max(self.body.start)..kept.end.min(self.body.end);
+        if !self.tidy || inner.start >= inner.end {
+            return self.input[kept].to_string();
+        }
+        let mut text = self.input[kept.start..inner.start].to_string();
+        let mut pos = inner.start;
+        for &escape in self.escapes.iter().filter(|&&escape| inner.contains(&escape)) {
+            text.push_str(&self.input[pos..escape]);
+            pos = escape + 1;
+        }
+        text.push_str(&self.input[pos..kept.end]);
+        text
+    }
+}
+
+// Punctuation that attaches to the following or preceding word, so removing
+// a range next to it never leaves a space inside, e.g. "(intro)" or "intro."
+const OPENING_PUNCTUATION: &str = "([{“‘¿¡";
+const CLOSING_PUNCTUATION: &str = ".,;:!?)]}”’…";
+
+impl Iterator for CleanPieces<'_> {
+    type Item = CleanPiece;
+
+    fn next(&mut self) -> Option<CleanPiece> {
+        if self.done {
+            return None;
+        }
+        let input = self.input;
+        let end = input.len();
+
+        // No ranges: the input passes through, only literal escapes change
+        if self.spans.is_empty() {
+            self.done = true;
+            return Some(CleanPiece {
+                kept: 0..end,
+                kept_text: self.kept_text(0..end),
+                removed: end..end,
+                joiner: false,
+            });
+        }
+
+        // Add remaining text after last range
+        if self.i >= self.spans.len() {
+            self.done = true;
+            return Some(CleanPiece {
+                kept: self.last_pos..end,
+                kept_text: self.kept_text(self.last_pos..end),
+                removed: end..end,
+                joiner: false,
+            });
+        }
+
+        let spans = &self.spans;
+        let span = &spans[self.i];
+        let text_start = self.last_pos;
+        let mut text_end = span.start;
+        let mut skip_to = span.end;
+
+        // A standalone connector left before the range has nothing to join once it is gone
+        if self.tidy
+            && let Some(connector) = connector_before(input, text_end, self.connectors)
+            && connector.start >= text_start.max(self.body.start)
+        {
+            text_end = connector.start;
+        }
         
-        // Check if there's a space before the timestamp
-        let before_space = text_end > 0 && input.as_bytes().get(text_end - 1) == Some(&b' ');
+        // Check if there's a space before the timestamp (not one already
+        // consumed after the previous range, nor the document's leading whitespace,
+        // nor the indentation of a line the timestamp opens)
+        let indented = input[..text_end]
+            .rsplit('\n')
+            .next()
+            .is_some_and(|line_head| line_head.chars().all(char::is_whitespace));
+        let before_space = text_end > text_start.max(self.body.start)
+            && !indented
+            && input.as_bytes().get(text_end - 1) == Some(&b' ');
         if before_space {
             text_end -= 1; // Exclude the space before
         }
         
-        // Check if this range is connected to the next one with " + "
-        if i + 1 < ranges.len() {
-            let between = &input[range.end_pos..ranges[i + 1].start_pos];
-            if between.trim() == "+" {
-                // Skip this range, the " + ", and the next range
-                skip_to = ranges[i + 1].end_pos;
-                i += 1; // Skip the next range in the loop
+        // Check if this range is connected to the following ones with " + " or a
+        // dash; the connector would be orphaned once both ranges are gone. Ranges
+        // only blanks apart go too, so the joiner is decided by the text after
+        // the whole run: "A (0:00-1:00) (1:00-2:00). B" -> "A. B"
+        while self.i + 1 < spans.len() {
+            let gap = &input[spans[self.i].end..spans[self.i + 1].start];
+            let between = gap.trim();
+            let blank = !gap.is_empty() && gap.chars().all(|c| c == ' ' || c == '\t');
+            if blank || self.connectors.contains(&between) || ["-", "–", "—"].contains(&between) {
+                // Skip this range, the connector, and the next range
+                skip_to = spans[self.i + 1].end;
+                self.i += 1; // Skip the next range in the loop
+            } else {
+                break;
             }
         }
         
-        // Check if there's a space after the timestamp
-        let after_space = skip_to < input.len() && input.as_bytes().get(skip_to) == Some(&b' ');
+        // Likewise a standalone connector after the last range of a chain
+        if self.tidy
+            && let Some(connector) = connector_after(input, skip_to, self.connectors)
+        {
+            skip_to = connector.end;
+        }
+
+        // Check if there's a space after the timestamp (not trailing whitespace)
+        let after_space = skip_to < self.body.end && input.as_bytes().get(skip_to) == Some(&b' ');
         if after_space {
             skip_to += 1; // Skip the space after
         }
         
-        // Add text before this range (excluding space before if present)
-        result.push_str(&input[text_start..text_end]);
-        
-        // If we removed spaces on both sides and we're joining words, add a single space
-        let before_char = if text_end > 0 { 
-            input[text_end - 1..].chars().next() 
-        } else { 
-            None 
+        // Put back a single space between the neighbours when one was removed,
+        // unless punctuation hugs the text ("intro (0:00-1:00)." -> "intro.");
+        // a range glued to words on both sides still gets a space between them
+        let before_char = if text_end > text_start {
+            input[..text_end].chars().next_back()
+        } else {
+            self.tail
         };
-        let after_char = if skip_to < input.len() { 
-            input[skip_to..].chars().next() 
-        } else { 
-            None 
+        let after_char = input[skip_to..].chars().next();
+        let needs_space = match (before_char, after_char) {
+            (Some(before), Some(after)) if before_space || after_space => {
+                !before.is_whitespace()
+                    && !after.is_whitespace()
+                    && !OPENING_PUNCTUATION.contains(before)
+                    && !CLOSING_PUNCTUATION.contains(after)
+            }
+            (Some(before), Some(after)) => before.is_alphanumeric() && after.is_alphanumeric(),
+            _ => false,
         };
         
-        // Check if we need to add a space (joining two alphanumeric characters)
-        let needs_space = before_char.is_some() 
-            && after_char.is_some()
-            && before_char.unwrap().is_alphanumeric()
-            && after_char.unwrap().is_alphanumeric()
-            && !before_space && !after_space;
-        
         if needs_space {
-            result.push(' ');
+            self.tail = Some(' ');
+        } else if text_end > text_start {
+            self.tail = before_char;
         }
-        
-        last_pos = skip_to;
-        i += 1;
+        self.last_pos = skip_to;
+        self.i += 1;
+        Some(CleanPiece {
+            kept: text_start..text_end,
+            // Add text before this range (excluding space before if present)
+            kept_text: self.kept_text(text_start..text_end),
+            removed: text_end..skip_to,
+            joiner: needs_space,
+        })
     }
-    
-    // Add remaining text after last range
-    result.push_str(&input[last_pos..]);
-    
-    // Clean up any leftover " + " that might be orphaned
-    result = result.replace(" + ", " ").replace("+ ", "").replace(" +", "");
-    
-    result
-}
\ No newline at end of file
+}
+
+/// Iterator over the cleaned text of a script, yielding one chunk per removed
+/// range (plus the trailing text), so output can be written incrementally.
+pub struct CleanSegments<'a> {
+    pieces: CleanPieces<'a>,
+}
+
+impl<'a> CleanSegments<'a> {
+    fn new(
+        input: &'a str,
+        ranges: &[ParsedRange],
+        adjustments: &[Adjustment],
+        escapes: &[usize],
+        options: &ParseOptions,
+    ) -> Self {
+        CleanSegments {
+            pieces: CleanPieces::new(input, ranges, adjustments, escapes, options),
+        }
+    }
+}
+
+impl Iterator for CleanSegments<'_> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        let piece = self.pieces.next()?;
+        let mut segment = piece.kept_text;
+        if piece.joiner {
+            segment.push(' ');
+        }
+        Some(segment)
+    }
+}
+
+/// A replacement of `start..end` (char offsets) with `text`. Offsets refer to
+/// the document after all preceding edits in the list have been applied.
+#[derive(Debug, Clone, Serialize)]
+pub struct TextEdit {
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+}
+
+// Turn cleaning pieces into in-order edits against the original text
+fn edits_from_pieces(input: &str, pieces: CleanPieces<'_>) -> Vec<TextEdit> {
+    let mut edits = Vec::new();
+    // Char offset of the current byte position, in the edited document
+    let mut cursor = 0;
+    let mut byte_pos = 0;
+
+    for piece in pieces {
+        cursor += input[byte_pos..piece.kept.start].chars().count();
+
+        let kept = &input[piece.kept.clone()];
+        let kept_len = kept.chars().count();
+        if piece.kept_text != kept {
+            edits.push(TextEdit {
+                start: cursor,
+                end: cursor + kept_len,
+                text: piece.kept_text.clone(),
+            });
+            cursor += piece.kept_text.chars().count();
+        } else {
+            cursor += kept_len;
+        }
+
+        if !piece.removed.is_empty() {
+            let text = if piece.joiner { " " } else { "" };
+            edits.push(TextEdit {
+                start: cursor,
+                end: cursor + input[piece.removed.clone()].chars().count(),
+                text: text.to_string(),
+            });
+            cursor += text.len();
+        }
+        byte_pos = piece.removed.end;
+    }
+
+    edits
+}
+
+/// A stretch of the original text that cleaning removed, in char offsets
+/// (`end` exclusive): the range with any blank and `+` connector removed along
+/// with it. A single space may take its place, as the matching `clean_edits`
+/// edit shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct RemovedSpan {
+    pub start: usize,
+    pub end: usize,
+}
+
+// The removed part of each cleaning piece, in the original text's char offsets;
+// pieces come in order, so the spans are ordered and never overlap
+fn spans_from_pieces(input: &str, pieces: CleanPieces<'_>) -> Vec<RemovedSpan> {
+    let mut spans = Vec::new();
+    // Char offset of `byte_pos` in the original text
+    let mut cursor = 0;
+    let mut byte_pos = 0;
+
+    for piece in pieces.filter(|piece| !piece.removed.is_empty()) {
+        let start = cursor + input[byte_pos..piece.removed.start].chars().count();
+        let end = start + input[piece.removed.clone()].chars().count();
+        spans.push(RemovedSpan { start, end });
+        cursor = end;
+        byte_pos = piece.removed.end;
+    }
+
+    spans
+}
+
+/// Removes only malformed timestamp-like text (what makes `calculate_durations`
+/// fail), keeping valid ranges and all other text exactly as written.
+pub fn clean_malformed(input: &str, options: &ParseOptions) -> String {
+    ParsedScript::parse(input, options).clean_malformed()
+}
+
+/// Same removals as `clean_malformed`, expressed as edits.
+pub fn clean_malformed_edits(input: &str, options: &ParseOptions) -> Vec<TextEdit> {
+    ParsedScript::parse(input, options).clean_malformed_edits()
+}
+
+/// The start of a cleaned script, from `clean_preview`.
+#[derive(Debug, Clone, Serialize)]
+pub struct CleanPreview {
+    /// The text with the first removals applied and the rest as written
+    pub cleaned: String,
+    /// Ranges removed so far
+    pub removed: usize,
+    /// Ranges left in the untouched rest of the text
+    pub remaining: usize,
+}
+
+/// `clean_script` stopped after `max_removals` removals, for a fast preview of
+/// a large document. Ranges chained with `+`, a dash or only blanks are
+/// removed together, as one removal. Text after the last removal is kept exactly as written;
+/// when nothing is left to remove the result equals `clean_script`.
+pub fn clean_preview(input: &str, options: &ParseOptions, max_removals: usize) -> CleanPreview {
+    let (ranges, adjustments, escapes) = scan_removals(input, options);
+    preview_from_ranges(input, &ranges, &adjustments, &escapes, options, max_removals)
+}
+
+fn preview_from_ranges(
+    input: &str,
+    ranges: &[ParsedRange],
+    adjustments: &[Adjustment],
+    escapes: &[usize],
+    options: &ParseOptions,
+    max_removals: usize,
+) -> CleanPreview {
+    let mut pieces = CleanPieces::new(input, ranges, adjustments, escapes, options);
+    let mut cleaned = String::new();
+    let mut removed = 0;
+    let mut removals = 0;
+    // Pieces and ranges both run in input order, so one cursor over the ranges
+    // counts those each removal takes
+    let mut next_range = 0;
+    while removals < max_removals
+        && let Some(piece) = pieces.next()
+    {
+        cleaned.push_str(&piece.kept_text);
+        if piece.joiner {
+            cleaned.push(' ');
+        }
+        if !piece.removed.is_empty() {
+            removals += 1;
+            while let Some(range) = ranges.get(next_range)
+                && range.start_pos < piece.removed.end
+            {
+                if piece.removed.contains(&range.start_pos) {
+                    removed += 1;
+                }
+                next_range += 1;
+            }
+        }
+    }
+
+    if removed < ranges.len() {
+        cleaned.push_str(&input[pieces.last_pos..]);
+    } else {
+        // Only the text after the last range is left, tidied as usual
+        cleaned.extend(CleanSegments { pieces });
+    }
+    CleanPreview {
+        cleaned,
+        removed,
+        remaining: ranges.len() - removed,
+    }
+}
+
+/// Same removals as `clean_script`, expressed as edits for an editor to apply.
+pub fn clean_edits(input: &str, options: &ParseOptions) -> Vec<TextEdit> {
+    let (ranges, adjustments, escapes) = scan_removals(input, options);
+    edits_from_pieces(input, CleanPieces::new(input, &ranges, &adjustments, &escapes, options))
+}
+
+/// Where in the original text each removal of `clean_script` lies, in order.
+pub fn clean_spans(input: &str, options: &ParseOptions) -> Vec<RemovedSpan> {
+    let (ranges, adjustments, escapes) = scan_removals(input, options);
+    spans_from_pieces(input, CleanPieces::new(input, &ranges, &adjustments, &escapes, options))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn seconds_per_line(output: &ParseOutput) -> Vec<u32> {
+        output.lines.iter().map(|line| line.result.seconds).collect()
+    }
+
+    #[test]
+    fn min_duration_drops_short_lines() {
+        let options = ParseOptions { min_duration_secs: Some(2), ..ParseOptions::default() };
+        let output = calculate_durations("(0:00-0:01) blip\n(0:00-1:00) intro", &options).unwrap();
+        assert_eq!(seconds_per_line(&output), [60]);
+        assert_eq!(output.lines[0].id, 2);
+        assert_eq!(output.total.seconds, 60);
+        assert_eq!(output.filtered, Some(1));
+    }
+
+    #[test]
+    fn min_duration_applies_to_whole_chains() {
+        // A short component never splits a " + " chain: the chain is kept whole
+        let options = ParseOptions { min_duration_secs: Some(2), ..ParseOptions::default() };
+        let output = calculate_durations("(0:00-0:01) + (0:00-1:00)", &options).unwrap();
+        assert_eq!(seconds_per_line(&output), [61]);
+        assert_eq!(output.filtered, Some(0));
+
+        // A chain short as a whole is dropped as a whole
+        let output = calculate_durations("(0:00-0:01) + (0:00-0:00)\n(0:00-0:05)", &options).unwrap();
+        assert_eq!(seconds_per_line(&output), [5]);
+        assert_eq!(output.filtered, Some(1));
+        assert_eq!(calculate_total("(0:00-0:01) + (0:00-0:00)\n(0:00-0:05)", &options).unwrap().0.seconds, 5);
+    }
+
+    #[test]
+    fn min_duration_unset_reports_no_filtered_count() {
+        let output = calculate_durations("(0:00-0:01)", &ParseOptions::default()).unwrap();
+        assert_eq!(output.filtered, None);
+        assert_eq!(seconds_per_line(&output), [1]);
+    }
+
+    #[test]
+    fn normalize_rewrites_ranges_in_canonical_form() {
+        let options = ParseOptions {
+            loose_spacing: true,
+            multipliers: true,
+            bare_durations: true,
+            range_lists: true,
+            ..ParseOptions::default()
+        };
+        let script = ParsedScript::parse("a ( 1 : 00 – 1:02:30 )x2 + 0:5 b (0:00-0:10, 0:20-0:30)", &options);
+        assert_eq!(
+            script.normalize().unwrap(),
+            "a (1:00-1:02:30)x2 + 0:05 b (0:00-0:10, 0:20-0:30)"
+        );
+        assert!(ParsedScript::parse("(0:00-1::30)", &ParseOptions::default()).normalize().is_err());
+    }
+
+
+    #[test]
+    fn input_without_ranges_is_an_empty_success() {
+        let output = calculate_durations("Just prose (and an aside).", &ParseOptions::default()).unwrap();
+        assert!(output.lines.is_empty());
+        assert_eq!(output.line_count(), 0);
+        assert_eq!(output.total.seconds, 0);
+        assert_eq!(output.total.format, "0:00");
+
+        let (total, lines, _) = calculate_total("", &ParseOptions::default()).unwrap();
+        assert_eq!((total.seconds, lines), (0, 0));
+    }
+
+    #[test]
+    fn malformed_input_is_still_an_error() {
+        assert!(calculate_durations("(0:00-1::00)", &ParseOptions::default()).is_err());
+    }
+
+
+    #[test]
+    fn streamed_clean_matches_collected_clean() {
+        let options = ParseOptions { bare_durations: true, ..ParseOptions::default() };
+        let inputs = [
+            "",
+            "no ranges at all",
+            "\n  (0:00-1:00) intro (1:00-2:00) + 0:30, outro (2:00-3:00).\n\n",
+            "(0:00-1:00)(1:00-1:30) - (2:00-2:30) tail (0:00-1::00) bad",
+        ];
+        for input in inputs {
+            let script = ParsedScript::parse(input, &options);
+            let segments: Vec<String> = script.clean_segments().collect();
+            assert_eq!(segments.concat(), clean_script(input, &options), "{:?}", input);
+            assert_eq!(segments.concat(), script.clean(), "{:?}", input);
+
+            let malformed: String = script.clean_malformed_segments().collect();
+            assert_eq!(malformed, clean_malformed(input, &options), "{:?}", input);
+        }
+    }
+
+
+    proptest! {
+        #[test]
+        fn formatted_durations_parse_back(seconds in any::<u32>()) {
+            prop_assert_eq!(parse_duration(&format_duration(seconds)), Ok(seconds));
+        }
+
+        #[test]
+        fn canonical_timestamps_parse_back(
+            hours in 0u32..=1000,
+            minutes in 0u32..=59,
+            seconds in 0u32..=59,
+        ) {
+            let timestamp = Timestamp { hours, minutes, seconds };
+            let text = timestamp.to_string_canonical();
+            let parsed = parse_timestamp_public(&text).unwrap();
+            prop_assert_eq!(parsed, timestamp);
+            prop_assert!(parsed.validate().is_ok());
+            prop_assert_eq!(parse_duration(&text), Ok(timestamp.to_seconds()));
+        }
+    }
+
+
+    #[test]
+    fn ranges_nested_in_prose_parentheses_are_found() {
+        let cases = [
+            ("(see the intro (0:00-1:00) for details)", 60),
+            ("((0:00-1:00))", 60),
+            ("(aside: (0:00-1:00) and (1:00-1:30))", 90),
+            ("(a (b (0:00-0:10) c) d)", 10),
+            ("\"quoted (0:00-0:20)\" (unrelated)", 20),
+        ];
+        for (input, seconds) in cases {
+            let output = calculate_durations(input, &ParseOptions::default())
+                .unwrap_or_else(|e| panic!("{:?}: {}", input, e));
+            assert_eq!(output.total.seconds, seconds, "{:?}", input);
+        }
+    }
+
+
+    #[test]
+    fn decimal_separator_only_changes_fractional_strings() {
+        let comma = FormatLocale { decimal_separator: Some(','), ..FormatLocale::default() };
+        assert_eq!(format_decimal_minutes(90, &comma), "1,5");
+        assert_eq!(format_decimal_minutes(90, &FormatLocale::default()), "1.5");
+        assert_eq!(format_decimal_minutes(120, &comma), "2");
+        assert_eq!(format_duration_human_localized(62_500, 1, &comma), "1 minute 2,5 seconds");
+        assert_eq!(format_duration_human_localized(62_500, 0, &comma), "1 minute 3 seconds");
+
+        let mut output = calculate_durations("(0:00-1:30)", &ParseOptions::default()).unwrap();
+        output.include_minutes(&comma);
+        assert_eq!(output.total.minutes, Some(1.5));
+        assert_eq!(output.total.minutes_format.as_deref(), Some("1,5"));
+        assert_eq!(output.total.format, "1:30");
+    }
+
+    #[test]
+    fn escaped_ranges_lose_both_backslashes() {
+        let options = ParseOptions::default();
+        assert_eq!(clean_script(r"Write \(0:00-1:00\) like this", &options), "Write (0:00-1:00) like this");
+        assert_eq!(clean_script(r"Write \(0:00-1:00) like this", &options), "Write (0:00-1:00) like this");
+        assert_eq!(
+            clean_script(r"\(0:00-1:00\) intro (0:00-0:10)", &options),
+            "(0:00-1:00) intro"
+        );
+        assert_eq!(ParsedScript::parse(r"Write \(0:00-1:00\)", &options).range_count(), 0);
+    }
+
+    #[test]
+    fn unrelated_escapes_are_left_alone() {
+        let options = ParseOptions::default();
+        assert_eq!(clean_script(r"Math \(x+1\) here", &options), r"Math \(x+1\) here");
+        assert_eq!(clean_script(r"Math \(x+1\) here (0:00-0:10) ok", &options), r"Math \(x+1\) here ok");
+    }
+    #[test]
+    fn line_durations_number_lines_and_elements_from_one() {
+        let options = ParseOptions::default();
+        let output = calculate_line_durations(&["(0:00-0:10) (0:10-0:20)", "(0:00-0:05)"], &options).unwrap();
+        let ids: Vec<usize> = output.lines.iter().map(|line| line.id).collect();
+        assert_eq!(ids, [1, 2, 3]);
+
+        let err = calculate_line_durations(&["(0:00-0:10)", "(0:20-0:10)"], &options).unwrap_err().message;
+        assert!(err.starts_with("Line 2: "), "{err}");
+    }
+    #[test]
+    fn total_errors_count_filtered_lines() {
+        let options = ParseOptions {
+            min_duration_secs: Some(5),
+            zero_length: Some(Severity::Error),
+            ..Default::default()
+        };
+        let input = "(0:00-0:01)\n(0:10-0:20)\n(0:30-0:30)";
+        let err = calculate_total(input, &options).unwrap_err();
+        assert_eq!(err, calculate_durations(input, &options).unwrap_err());
+        assert!(err.message.contains('3'), "{err}");
+
+        let options = ParseOptions {
+            min_duration_secs: Some(5),
+            max_total_secs: Some(15),
+            ..Default::default()
+        };
+        let input = "(0:00-0:01)\n(0:10-0:20)\n(0:30-0:40)";
+        let err = calculate_total(input, &options).unwrap_err();
+        assert_eq!(err, calculate_durations(input, &options).unwrap_err());
+        assert!(err.message.contains("at line 3"), "{err}");
+    }
+    #[test]
+    fn bracketed_asides_do_not_leave_a_range_unterminated() {
+        let options = ParseOptions::default();
+        assert!(calculate_durations("(2:00 - 3:00 (approx))", &options).is_ok());
+        assert!(calculate_durations("(2:00 - 3:00 (approx) roughly)", &options).is_ok());
+
+        let err = calculate_durations("(2:00 - 3:00 (approx)\nnext", &options).unwrap_err().message;
+        assert!(err.starts_with("Unterminated timestamp range"), "{err}");
+        let err = calculate_durations("(0:00-1:30 oops", &options).unwrap_err().message;
+        assert!(err.starts_with("Unterminated timestamp range"), "{err}");
+    }
+    #[test]
+    fn oversized_timestamps_saturate() {
+        let timestamp = Timestamp { hours: u32::MAX, minutes: 0, seconds: 0 };
+        assert_eq!(timestamp.checked_seconds(), None);
+        assert_eq!(timestamp.to_seconds(), u32::MAX);
+        let timestamp = Timestamp { hours: 0, minutes: 71_582_789, seconds: 16 };
+        assert_eq!(timestamp.to_seconds(), u32::MAX);
+        assert_eq!(Timestamp { hours: 1, minutes: 2, seconds: 3 }.to_seconds(), 3723);
+    }
+    #[test]
+    fn cleaning_keeps_leading_and_trailing_newlines() {
+        let options = ParseOptions::default();
+        assert_eq!(clean_script("\n\nIntro (0:00-1:00) text\n\n", &options), "\n\nIntro text\n\n");
+        assert_eq!(clean_script("\n(0:00-1:00) Intro\n", &options), "\nIntro\n");
+        assert_eq!(clean_script("  Intro (0:00-1:00) \n", &options), "  Intro \n");
+        assert_eq!(clean_script("\r\nIntro (0:00-1:00)\r\n", &options), "\r\nIntro\r\n");
+    }
+    #[test]
+    fn invalid_seconds_suggest_the_intended_time() {
+        let options = ParseOptions::default();
+        let err = calculate_durations("(0:00-0:90)", &options).unwrap_err().message;
+        assert_eq!(err, "Invalid timestamp range: (0:00-0:90) (seconds 90 exceeds 59, did you mean 1:30?)");
+        let err = calculate_durations("(59:75-1:05:00)", &options).unwrap_err().message;
+        assert!(err.ends_with("(seconds 75 exceeds 59, did you mean 1:00:15?)"), "{err}");
+    }
+    #[test]
+    fn canonical_strings_pad_only_minutes_after_hours_and_seconds() {
+        let canonical = |hours, minutes, seconds| Timestamp { hours, minutes, seconds }.to_string_canonical();
+        assert_eq!(canonical(0, 0, 0), "0:00");
+        assert_eq!(canonical(0, 0, 7), "0:07");
+        assert_eq!(canonical(0, 5, 9), "5:09");
+        assert_eq!(canonical(0, 59, 59), "59:59");
+        assert_eq!(canonical(1, 0, 0), "1:00:00");
+        assert_eq!(canonical(1, 5, 9), "1:05:09");
+        assert_eq!(canonical(100, 0, 1), "100:00:01");
+        // Unbounded fields are printed as they are, without carrying
+        assert_eq!(canonical(0, 90, 0), "90:00");
+        assert_eq!(canonical(0, 1, 75), "1:75");
+        assert_eq!(canonical(2, 120, 5), "2:120:05");
+    }
+    #[test]
+    fn range_lists_reject_parts_out_of_order() {
+        let options = ParseOptions { range_lists: true, ..ParseOptions::default() };
+        let err = calculate_durations("(2:00-2:30, 0:00-1:00)", &options).unwrap_err().message;
+        assert_eq!(err, "Invalid timestamp range: (2:00-2:30, 0:00-1:00) (a part starts before the previous one ends)");
+        assert!(calculate_durations("(0:00-1:00, 0:30-1:30)", &options).is_err());
+        assert_eq!(seconds_per_line(&calculate_durations("(0:00-1:00, 1:00-1:30)", &options).unwrap()), [90]);
+        assert!(longest_span("(2:00-2:30, 0:00-1:00)", &options).is_err());
+    }
+
+    #[test]
+    fn range_list_gaps_are_not_covered() {
+        let options = ParseOptions { range_lists: true, ..ParseOptions::default() };
+        let (spans, _) = covered_spans("(0:00-1:00, 2:00-2:30)", &options).unwrap();
+        let lengths: Vec<u32> = spans.iter().map(|span| span.length.seconds).collect();
+        assert_eq!(lengths, [60, 30]);
+        assert_eq!(longest_span("(0:00-1:00, 2:00-2:30)", &options).unwrap().0.unwrap().length.seconds, 60);
+    }
+    #[test]
+    fn adjacent_removals_are_joined_by_what_follows_the_run() {
+        let options = ParseOptions::default();
+        assert_eq!(clean_script("A (0:00-1:00) (0:01-0:02). B", &options), "A. B");
+        assert_eq!(clean_script("A (0:00-1:00) (0:01-0:02), B", &options), "A, B");
+        assert_eq!(clean_script("A (0:00-1:00)\t(0:01-0:02); B", &options), "A; B");
+        assert_eq!(clean_script("(A (0:00-1:00) (0:01-0:02)) B", &options), "(A) B");
+        assert_eq!(clean_script("A (0:00-1:00) (0:01-0:02) B", &options), "A B");
+    }
+    #[test]
+    fn multipliers_parse_with_or_without_a_blank() {
+        let options = ParseOptions { multipliers: true, ..ParseOptions::default() };
+        for input in ["(0:00-0:30)x4", "(0:00-0:30) x4", "(0:00-0:30)\tX4", "(0:00-0:30) ×4"] {
+            assert_eq!(seconds_per_line(&calculate_durations(input, &options).unwrap()), [120], "{input}");
+        }
+        // A count glued to more text is prose, and the range keeps its length
+        assert_eq!(seconds_per_line(&calculate_durations("(0:00-0:30) x4k", &options).unwrap()), [30]);
+    }
+
+    #[test]
+    fn cleaning_removes_multipliers_with_their_blank() {
+        let options = ParseOptions { multipliers: true, ..ParseOptions::default() };
+        assert_eq!(clean_script("Chorus (0:00-0:30)x4 ends.", &options), "Chorus ends.");
+        assert_eq!(clean_script("Chorus (0:00-0:30) x4 ends.", &options), "Chorus ends.");
+        assert_eq!(clean_script("Chorus (0:00-0:30) x4.", &options), "Chorus.");
+        assert_eq!(clean_script("Chorus (0:00-0:30) x4k sales", &options), "Chorus x4k sales");
+    }
+    #[test]
+    fn padded_minutes_apply_to_signed_deltas() {
+        let mut output = calculate_durations("(0:00-0:30)\n(0:30-2:00)", &ParseOptions::default()).unwrap();
+        output.set_target(125);
+        output.set_line_target(60);
+        output.localize(&FormatLocale { pad_minutes: true, ..FormatLocale::default() });
+        assert_eq!(output.total.format, "02:00");
+        assert_eq!(output.remaining.unwrap().format, "00:05");
+        let deltas: Vec<String> = output.lines.iter().map(|line| line.target_delta.clone().unwrap().format).collect();
+        assert_eq!(deltas, ["00:30", "-00:30"]);
+        assert_eq!(format_signed_duration_localized(-754, &FormatLocale::default()), "-12:34");
+    }
+    #[test]
+    fn nearest_rank_picks_the_ceiling_rank() {
+        let ten: Vec<u32> = (1..=10).collect();
+        assert_eq!(nearest_rank(&ten, 90.0), 9);
+        assert_eq!(nearest_rank(&ten, 100.0), 10);
+        assert_eq!(nearest_rank(&ten, 10.0), 1);
+        assert_eq!(nearest_rank(&ten, 0.1), 1);
+        assert_eq!(nearest_rank(&ten, 91.0), 10);
+        // Exact for whole percentiles where dividing first would round up
+        let twenty: Vec<u32> = (1..=20).collect();
+        assert_eq!(nearest_rank(&twenty, 95.0), 19);
+        assert_eq!(nearest_rank(&[4, 7, 9, 12], 50.0), 7);
+        assert_eq!(nearest_rank(&[42], 1.0), 42);
+    }
+
+    #[test]
+    fn stats_validate_percentiles() {
+        let mut output = calculate_durations("(0:00-0:10)\n(0:00-0:30)\n(0:00-0:20)", &ParseOptions::default()).unwrap();
+        for p in [0.0, -5.0, 100.5, f64::NAN] {
+            assert!(output.compute_stats(&[p]).is_err(), "{p}");
+        }
+        output.compute_stats(&[50.0, 100.0]).unwrap();
+        let stats = output.stats.unwrap();
+        assert_eq!((stats.count, stats.min.seconds, stats.median.seconds, stats.max.seconds), (3, 10, 20, 30));
+        let values: Vec<u32> = stats.percentiles.iter().map(|p| p.duration.seconds).collect();
+        assert_eq!(values, [20, 30]);
+
+        let mut empty = calculate_durations("no ranges", &ParseOptions::default()).unwrap();
+        empty.compute_stats(&[50.0]).unwrap();
+        assert!(empty.stats.is_none());
+    }
+    #[test]
+    fn cleaning_keeps_indentation_before_a_leading_range() {
+        let options = ParseOptions::default();
+        assert_eq!(clean_script("Intro\n\t(0:00-1:00) Tabbed line\n", &options), "Intro\n\tTabbed line\n");
+        assert_eq!(clean_script("Intro\n    (0:00-1:00) Spaced line", &options), "Intro\n    Spaced line");
+        assert_eq!(clean_script("Intro\n \t(0:00-1:00) Mixed", &options), "Intro\n \tMixed");
+        assert_eq!(clean_script("  (0:00-1:00) First line", &options), "  First line");
+    }
+    #[test]
+    fn orphan_connectors_are_flagged_and_removed() {
+        let options = ParseOptions::default();
+        let warnings = |input: &str| calculate_durations(input, &options).unwrap().lines[0].warnings.clone();
+
+        assert_eq!(warnings("+ (0:00-1:00) intro"), ["Connector + before (0:00-1:00) has nothing to join"]);
+        assert_eq!(clean_script("+ (0:00-1:00) intro", &options), "intro");
+        assert_eq!(warnings("Intro (0:00-1:00) +\nNext"), ["Connector + after (0:00-1:00) has nothing to join"]);
+        assert_eq!(clean_script("Intro (0:00-1:00) +\nNext", &options), "Intro\nNext");
+        assert_eq!(
+            warnings("\t+ (0:00-1:00) + (1:00-2:00) + "),
+            [
+                "Connector + before (0:00-1:00) has nothing to join",
+                "Connector + after (1:00-2:00) has nothing to join"
+            ]
+        );
+
+        // A `+` joining ranges, or glued to a word, is no orphan
+        assert!(warnings("(0:00-1:00) + (1:00-2:00)").is_empty());
+        assert!(warnings("C++ (0:00-1:00)").is_empty());
+        assert_eq!(clean_script("C++ (0:00-1:00) tips", &options), "C++ tips");
+    }
+    #[test]
+    fn round_total_rounds_up_or_to_the_nearest_step() {
+        let rounded = |seconds: u32, step: u32, mode: RoundMode| {
+            let mut output = calculate_durations("(0:00-0:01)", &ParseOptions::default()).unwrap();
+            output.total = DurationResult::new(seconds);
+            output.round_total(step, mode).map(|()| output.rounded_total.unwrap().seconds)
+        };
+        assert_eq!(rounded(12 * 60 + 1, 60, RoundMode::Up), Ok(13 * 60));
+        assert_eq!(rounded(12 * 60, 60, RoundMode::Up), Ok(12 * 60));
+        assert_eq!(rounded(12 * 60 + 29, 60, RoundMode::Nearest), Ok(12 * 60));
+        assert_eq!(rounded(12 * 60 + 30, 60, RoundMode::Nearest), Ok(13 * 60));
+        assert_eq!(rounded(0, 60, RoundMode::Up), Ok(0));
+        assert!(rounded(100, 0, RoundMode::Up).is_err());
+        // Rounding past the top saturates rather than wrapping
+        assert_eq!(rounded(u32::MAX, 60, RoundMode::Up), Ok(u32::MAX));
+        assert_eq!(rounded(u32::MAX, 100, RoundMode::Nearest), Ok(u32::MAX));
+        assert_eq!(rounded(u32::MAX - 10, 60, RoundMode::Nearest), Ok(u32::MAX - 15));
+        assert_eq!(rounded(u32::MAX, u32::MAX, RoundMode::Nearest), Ok(u32::MAX));
+    }
+    #[test]
+    fn human_durations_round_before_naming_units() {
+        assert_eq!(format_duration_human(59_960, 1), "1 minute");
+        assert_eq!(format_duration_human(1_950, 1), "2 seconds");
+        assert_eq!(format_duration_human(1_949, 1), "1.9 seconds");
+        assert_eq!(format_duration_human(62_345, 1), "1 minute 2.3 seconds");
+        assert_eq!(format_duration_human(62_345, 9), "1 minute 2.345 seconds");
+        assert_eq!(format_duration_human_whole(1_950), "2 seconds");
+        assert_eq!(format_duration_human_whole(3_599_500), "1 hour");
+    }
+
+    #[test]
+    fn human_durations_are_singular_only_for_exactly_one() {
+        assert_eq!(format_duration_human_whole(0), "0 seconds");
+        assert_eq!(format_duration_human_whole(1_000), "1 second");
+        assert_eq!(format_duration_human(1_000, 2), "1 second");
+        assert_eq!(format_duration_human(1_500, 1), "1.5 seconds");
+        assert_eq!(format_duration_human(100, 1), "0.1 seconds");
+        assert_eq!(format_duration_human_whole(3_661_000), "1 hour 1 minute 1 second");
+        assert_eq!(format_duration_human_whole(7_322_000), "2 hours 2 minutes 2 seconds");
+        assert_eq!(format_duration_human_whole(3_600_000 + 2_000), "1 hour 2 seconds");
+    }
+    #[test]
+    fn a_megabyte_of_digits_and_colons_scans_in_linear_time() {
+        // One unclosed bracket before ~1 MB of "1:2:3:...": every pattern tried
+        // at it must stay linear. The bound is generous, but quadratic work
+        // would take minutes
+        let input = format!("({}", "1:2:3:".repeat(175_000));
+        let start = std::time::Instant::now();
+        let _ = calculate_durations(&input, &ParseOptions::default());
+        let cleaned = clean_script(&input, &ParseOptions::default());
+        assert_eq!(cleaned, input);
+        assert!(start.elapsed() < std::time::Duration::from_secs(10), "{:?}", start.elapsed());
+    }
+    #[test]
+    fn large_minutes_mix_with_hour_layouts() {
+        let options = ParseOptions { warn_large_minutes: true, ..ParseOptions::default() };
+        let seconds = |input: &str| seconds_per_line(&calculate_durations(input, &options).unwrap());
+        assert_eq!(seconds("(1:30:00-95:00)"), [300]);
+        assert_eq!(seconds("(95:00-1:40:00)"), [300]);
+        assert_eq!(seconds("(1:00:00-61:00)"), [60]);
+
+        let output = calculate_durations("(1:00:00-61:00)", &options).unwrap();
+        assert_eq!(output.lines[0].warnings, ["Range (1:00:00-61:00) has 61 minutes, counted as 1:01:00"]);
+
+        // Without the option, minutes over 59 are still an error
+        assert!(calculate_durations("(1:30:00-95:00)", &ParseOptions::default()).is_err());
+    }
+
+    #[test]
+    fn minutes_too_large_for_seconds_are_an_error() {
+        let options = ParseOptions { warn_large_minutes: true, ..ParseOptions::default() };
+        let err = calculate_durations("(0:00-71582789:00)", &options).unwrap_err().message;
+        assert_eq!(err, "Invalid timestamp range: (0:00-71582789:00) (time 71582789:00 is too large)");
+    }
+
+    #[test]
+    fn skipped_spans_are_reported_by_every_range_scan() {
+        let options = ParseOptions { skip_malformed: true, ..ParseOptions::default() };
+        let input = "(0:00-1::00) intro\n(0:00-0:10) body";
+        let expected = vec!["Malformed timestamp: (0:00-1::00)".to_string()];
+
+        let warnings = calculate_durations(input, &options).unwrap().warnings;
+        assert_eq!(warnings.as_ref(), Some(&expected));
+        let (total, lines, skipped) = calculate_total(input, &options).unwrap();
+        assert_eq!((total.seconds, lines, skipped), (10, 1, expected.clone()));
+        assert_eq!(check_order(input, &options).unwrap().warnings, Some(expected.clone()));
+        assert_eq!(list_ranges(input, &options).unwrap().1, expected);
+        assert_eq!(longest_span(input, &options).unwrap().1, expected);
+
+        // Without the option the span is still an error, and nothing is skipped
+        assert!(calculate_total(input, &ParseOptions::default()).is_err());
+        assert!(calculate_total("(0:00-0:10)", &options).unwrap().2.is_empty());
+    }
+
+    #[test]
+    fn lenient_skips_one_bad_range_and_joins_words() {
+        let options = ParseOptions::lenient();
+        assert!(options.range_lists && options.skip_malformed && options.skip_invalid && options.word_connectors);
+        let (span, skipped) = longest_span("(2:00-2:30, 0:00-1:00)", &options).unwrap();
+        assert!(span.is_none());
+        assert!(skipped[0].contains("a part starts before the previous one ends"), "{skipped:?}");
+
+        let output = calculate_durations("(0:00-1:00) and (1:00-1:30)\n(2:00-1:00)\n(0:00-0:10)", &options).unwrap();
+        assert_eq!(seconds_per_line(&output), vec![90, 10]);
+        assert_eq!(
+            output.warnings,
+            Some(vec!["Invalid timestamp range: (2:00-1:00) (end time is before start time)".to_string()])
+        );
+        // Without the preset the same range fails the whole script
+        assert!(calculate_durations("(2:00-1:00)", &ParseOptions::default()).is_err());
+    }
+
+    #[test]
+    fn word_connectors_join_ranges_like_plus() {
+        let options = ParseOptions { word_connectors: true, bare_durations: true, ..ParseOptions::default() };
+        let input = "Intro (0:00-1:00) and (2:00-2:30) & 0:10 end\nband (0:00-0:05)\n(0:00-0:02) &";
+        let output = calculate_durations(input, &options).unwrap();
+        assert_eq!(seconds_per_line(&output), vec![100, 5, 2]);
+        assert_eq!(output.lines[2].warnings, vec!["Connector & after (0:00-0:02) has nothing to join"]);
+        assert_eq!(clean_script(input, &options), "Intro end\nband\n");
+
+        // Off by default, so "and" is prose between two lines
+        let output = calculate_durations(input, &ParseOptions::default()).unwrap();
+        assert_eq!(seconds_per_line(&output), vec![60, 30, 5, 2]);
+    }
+
+    #[test]
+    fn huge_ranges_saturate_line_and_grand_totals() {
+        let big = "(0:00-1000000:00:00)";
+        let chained = format!("{big} + {big}");
+        let output = calculate_durations(&chained, &ParseOptions::default()).unwrap();
+        assert_eq!((output.lines[0].result.seconds, output.total.seconds), (u32::MAX, u32::MAX));
+
+        let lines = vec![big; 5];
+        let output = calculate_durations(&lines.join("\n"), &ParseOptions::default()).unwrap();
+        assert_eq!(output.total.seconds, u32::MAX);
+        assert_eq!(calculate_total(&lines.join("\n"), &ParseOptions::default()).unwrap().0.seconds, u32::MAX);
+        assert_eq!(calculate_line_durations(&lines, &ParseOptions::default()).unwrap().total.seconds, u32::MAX);
+    }
+
+    #[test]
+    fn total_duration_skips_errored_ranges_and_saturates() {
+        let options = ParseOptions::default();
+        let input = "(0:00-1:00)\n(2:00-1:00)\n(1:00-1:30)";
+        let (ranges, _) = list_ranges(input, &options).unwrap();
+        assert!(ranges[1].error.is_some());
+        assert_eq!(total_duration(&ranges).seconds, 90);
+
+        let valid = "(0:00-1:00) + (1:00-1:30)\n(2:00-2:10)";
+        let (ranges, _) = list_ranges(valid, &options).unwrap();
+        assert_eq!(total_duration(&ranges).seconds, calculate_durations(valid, &options).unwrap().total.seconds);
+
+        let huge = ["(0:00-1000000:00:00)"; 5].join("\n");
+        let (ranges, _) = list_ranges(&huge, &options).unwrap();
+        assert_eq!(total_duration(&ranges).seconds, u32::MAX);
+    }
+
+    #[test]
+    fn errors_carry_the_category_they_were_raised_with() {
+        let category = |input: &str, options: &ParseOptions| calculate_durations(input, options).unwrap_err().category;
+        let options = ParseOptions::default();
+        assert_eq!(category("(0:20-0:10)", &options), ErrorCategory::EndBeforeStart);
+        assert_eq!(category("(0:00-0:90)", &options), ErrorCategory::InvalidSeconds);
+        assert_eq!(category("(0:00-1::00)", &options), ErrorCategory::Malformed);
+        assert_eq!(category("(0:00-1:30 oops", &options), ErrorCategory::Malformed);
+        let large = ParseOptions { warn_large_minutes: true, ..ParseOptions::default() };
+        assert_eq!(category("(0:00-71582789:00)", &large), ErrorCategory::TooLarge);
+        let capped = ParseOptions { max_total_secs: Some(5), ..ParseOptions::default() };
+        assert_eq!(category("(0:00-0:10)", &capped), ErrorCategory::TotalTooLong);
+        let scan_limit = ParseOptions { max_candidates: Some(1), ..ParseOptions::default() };
+        assert_eq!(category("(0:00-0:01) (0:00-0:02)", &scan_limit), ErrorCategory::TooManyBrackets);
+
+        // Context added to the message does not change the category
+        let err = calculate_line_durations(&["(0:00-0:10)", "(0:20-0:10)"], &options).unwrap_err();
+        assert!(err.message.starts_with("Line 2: "), "{err}");
+        assert_eq!(err.category, ErrorCategory::EndBeforeStart);
+    }
+
+    #[test]
+    fn clean_preview_counts_the_ranges_each_removal_takes() {
+        let options = ParseOptions::default();
+        let input = "A (0:00-0:10) + (0:10-0:20) b (0:20-0:30) c (0:30-0:40) d";
+        let preview = clean_preview(input, &options, 2);
+        assert_eq!(preview.cleaned, "A b c (0:30-0:40) d");
+        assert_eq!((preview.removed, preview.remaining), (3, 1));
+
+        let preview = clean_preview(input, &options, 10);
+        assert_eq!(preview.cleaned, clean_script(input, &options));
+        assert_eq!((preview.removed, preview.remaining), (4, 0));
+    }
+
+    #[test]
+    fn unicode_digit_messages_and_cleaning_keep_the_original_text() {
+        let options = ParseOptions { unicode_digits: true, ..ParseOptions::default() };
+        let malformed = "(０:００-１::００) 導入";
+        let error = calculate_durations(malformed, &options).unwrap_err();
+        assert_eq!(error.message, "Malformed timestamp: (０:００-１::００)");
+        let unterminated = "導入 (０:００-１:３０ 本編";
+        let error = calculate_durations(unterminated, &options).unwrap_err();
+        assert_eq!(error.message, "Unterminated timestamp range at line 1, column 4: (０:００-１:３０ 本編");
+        let skipping = ParseOptions { skip_malformed: true, ..options.clone() };
+        let warnings = calculate_durations(&format!("{malformed}\n(０:００-０:１０) 本編"), &skipping)
+            .unwrap()
+            .warnings;
+        assert_eq!(warnings, Some(vec!["Malformed timestamp: (０:００-１::００)".to_string()]));
+
+        let cleaned = clean_script("導入 (０:００-０:１０) 本編\n（注）(１:００-１:３０)：終わり", &options);
+        assert_eq!(cleaned, "導入 本編\n（注）：終わり");
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_and_sequential_scans_agree() {
+        let options = ParseOptions {
+            interval_notation: true,
+            nudges: true,
+            bare_durations: true,
+            range_lists: true,
+            ..ParseOptions::default()
+        };
+        let lines = [
+            "Shot (0:00-0:05) + 0:03",
+            "(see (0:10-0:20) for details)",
+            "(0:00-1::00) broken",
+            "\\(0:00-1:00\\) literal",
+            "(+0:02) nudge [0:00, 0:30) interval",
+            "(0:00-0:10, 0:20-0:30) list (0:30-0:10) backwards",
+            "(0:00-1:30 unterminated",
+        ];
+        let input = lines.iter().cycle().take(PARALLEL_MIN_BRACKETS).cloned().collect::<Vec<_>>().join("\n");
+
+        let summary = |scan: ScanResult| {
+            let ranges: Vec<_> = scan
+                .ranges
+                .iter()
+                .map(|range| (range.start_pos, range.end_pos, range.duration, range_error_message(range)))
+                .collect();
+            let malformed: Vec<_> =
+                scan.malformed.into_iter().map(|span| (span.start_pos, span.end_pos, span.message)).collect();
+            let adjustments: Vec<_> = scan.adjustments.into_iter().map(|adjustment| adjustment.span).collect();
+            (ranges, malformed, adjustments, scan.escapes)
+        };
+        let sequential = summary(scan_prepared_ranges(&input, &options, None));
+        let parallel = summary(scan_prepared_ranges(&input, &options, prepare_ranges(&input, &options, 0)));
+        assert!(!sequential.0.is_empty() && !sequential.1.is_empty() && !sequential.2.is_empty());
+        assert_eq!(parallel, sequential);
+        assert_eq!(summary(scan_ranges(&input, &options)), sequential);
+    }
+}
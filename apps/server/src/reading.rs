@@ -0,0 +1,72 @@
+use serde::Serialize;
+
+use crate::parser::{
+    DurationDelta, DurationResult, ErrorCategory, ParseOptions, ScriptError, calculate_durations, clean_script,
+};
+
+/// Speaking rates accepted by `estimate_reading`, in words per minute.
+pub const WPM_RANGE: std::ops::RangeInclusive<u32> = 1..=1000;
+
+/// How the spoken estimate compares with the time the ranges mark out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReadingFit {
+    /// The text takes longer to read than the marked time
+    Overrun,
+    /// The text is read before the marked time is up
+    Underrun,
+    Exact,
+}
+
+/// A script's estimated reading time next to its marked total.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReadingEstimate {
+    pub words: usize,
+    pub wpm: u32,
+    pub estimated: DurationResult,
+    pub marked: DurationResult,
+    // Estimated minus marked: positive when the narration overruns
+    pub difference: DurationDelta,
+    pub fit: ReadingFit,
+}
+
+// Whitespace-separated tokens holding at least one letter or digit, so stray
+// punctuation and leftover connectors are not counted as words
+pub(crate) fn word_count(text: &str) -> usize {
+    text.split_whitespace()
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .count()
+}
+
+/// Estimates how long the prose of a script takes to read aloud at `wpm`,
+/// counting the words left once the ranges are removed, and compares it with
+/// the total of the ranges. The estimate is rounded to whole seconds.
+pub fn estimate_reading(input: &str, wpm: u32, options: &ParseOptions) -> Result<ReadingEstimate, ScriptError> {
+    if !WPM_RANGE.contains(&wpm) {
+        let message = format!(
+            "Invalid wpm: {} (must be between {} and {})",
+            wpm,
+            WPM_RANGE.start(),
+            WPM_RANGE.end()
+        );
+        return Err(ScriptError::new(ErrorCategory::Other, message));
+    }
+
+    let marked = calculate_durations(input, options)?.total;
+    let words = word_count(&clean_script(input, options));
+    let estimated = ((words as u64 * 60 + wpm as u64 / 2) / wpm as u64).min(u32::MAX as u64) as u32;
+    let fit = match estimated.cmp(&marked.seconds) {
+        std::cmp::Ordering::Greater => ReadingFit::Overrun,
+        std::cmp::Ordering::Less => ReadingFit::Underrun,
+        std::cmp::Ordering::Equal => ReadingFit::Exact,
+    };
+
+    Ok(ReadingEstimate {
+        words,
+        wpm,
+        difference: DurationDelta::between(marked.seconds, estimated),
+        estimated: DurationResult::new(estimated),
+        marked,
+        fit,
+    })
+}
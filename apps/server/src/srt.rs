@@ -0,0 +1,211 @@
+use nom::{
+    IResult, Parser,
+    branch::alt,
+    bytes::complete::{tag, take_while_m_n},
+    character::complete::{digit1, space0},
+    combinator::{all_consuming, map_res, opt, verify},
+    sequence::preceded,
+};
+
+use crate::parser::{DurationResult, LineResult, ParseOutput};
+
+// Cue time in milliseconds from the start of the file
+fn to_millis(hours: u32, minutes: u32, seconds: u32, millis: u32) -> u64 {
+    ((hours as u64 * 60 + minutes as u64) * 60 + seconds as u64) * 1000 + millis as u64
+}
+
+fn fixed_digits(count: usize) -> impl Fn(&str) -> IResult<&str, u32> {
+    move |input| {
+        map_res(
+            take_while_m_n(count, count, |c: char| c.is_ascii_digit()),
+            |s: &str| s.parse::<u32>(),
+        )
+        .parse(input)
+    }
+}
+
+fn number(input: &str) -> IResult<&str, u32> {
+    map_res(digit1, |s: &str| s.parse::<u32>()).parse(input)
+}
+
+// Canonical SRT time: HH:MM:SS,mmm with minutes and seconds capped at 59
+fn strict_time(input: &str) -> IResult<&str, u64> {
+    let sexagesimal = || verify(fixed_digits(2), |value: &u32| *value <= 59);
+    let (input, (hours, _, minutes, _, seconds, _, millis)) = (
+        fixed_digits(2),
+        tag(":"),
+        sexagesimal(),
+        tag(":"),
+        sexagesimal(),
+        tag(","),
+        fixed_digits(3),
+    )
+        .parse(input)?;
+    Ok((input, to_millis(hours, minutes, seconds, millis)))
+}
+
+// Fractional part of a tolerant time, scaled to milliseconds ("5" -> 500)
+fn fraction(input: &str) -> IResult<&str, u32> {
+    let (rest, digits) = preceded(
+        alt((tag(","), tag("."))),
+        take_while_m_n(1, 3, |c: char| c.is_ascii_digit()),
+    )
+    .parse(input)?;
+    let scale = 10u32.pow(3 - digits.len() as u32);
+    Ok((rest, digits.parse::<u32>().unwrap_or(0) * scale))
+}
+
+// Sloppy SRT time: optional/unpadded hours, '.' or ',' before optional millis
+fn tolerant_time(input: &str) -> IResult<&str, u64> {
+    let (input, first) = number(input)?;
+    let (input, _) = tag(":").parse(input)?;
+    let (input, second) = number(input)?;
+    let (input, third) = opt(preceded(tag(":"), number)).parse(input)?;
+    let (input, millis) = opt(fraction).parse(input)?;
+    let (hours, minutes, seconds) = match third {
+        Some(seconds) => (first, second, seconds),
+        None => (0, first, second),
+    };
+    Ok((input, to_millis(hours, minutes, seconds, millis.unwrap_or(0))))
+}
+
+fn timing_line(input: &str, strict: bool) -> Option<(u64, u64)> {
+    let result = if strict {
+        all_consuming((strict_time, tag(" --> "), strict_time))
+            .map(|(start, _, end)| (start, end))
+            .parse(input)
+    } else {
+        all_consuming((tolerant_time, space0, tag("-->"), space0, tolerant_time, space0))
+            .map(|(start, _, _, _, end, _)| (start, end))
+            .parse(input)
+    };
+    result.ok().map(|(_, times)| times)
+}
+
+fn rounded_seconds(millis: u64) -> u32 {
+    ((millis + 500) / 1000) as u32
+}
+
+/// Parses SubRip (`.srt`) subtitles into one line per cue.
+///
+/// With `strict` set, cue timing must be canonical (`00:00:01,000 --> 00:00:04,000`);
+/// otherwise unpadded or missing hours and `.` millis are accepted. Durations are
+/// rounded to whole seconds per cue, while the total is rounded from the exact sum.
+pub fn parse_srt(input: &str, strict: bool) -> Result<ParseOutput, String> {
+    let normalized = input.trim_start_matches('\u{feff}').replace("\r\n", "\n");
+    let mut lines = Vec::new();
+    let mut total_millis = 0u64;
+
+    let blocks = normalized
+        .split("\n\n")
+        .map(str::trim)
+        .filter(|block| !block.is_empty());
+
+    for (position, block) in blocks.enumerate() {
+        let mut block_lines = block.lines();
+        let first = block_lines.next().unwrap_or("").trim();
+
+        // Tolerant mode allows cues without an index line
+        let (index, timing) = match first.parse::<usize>() {
+            Ok(index) => (index, block_lines.next().unwrap_or("").trim()),
+            Err(_) if !strict && first.contains("-->") => (position + 1, first),
+            Err(_) => {
+                return Err(format!("Cue {}: missing cue index", position + 1));
+            }
+        };
+
+        let (start, end) = timing_line(timing, strict).ok_or_else(|| {
+            format!("Cue {}: malformed cue timing: {}", index, timing)
+        })?;
+
+        if end < start {
+            return Err(format!(
+                "Cue {}: invalid cue timing: {} (end time is before start time)",
+                index, timing
+            ));
+        }
+
+        let duration = end - start;
+        total_millis += duration;
+        let seconds = rounded_seconds(duration);
+        lines.push(LineResult {
+            id: index,
+            input: timing.to_string(),
+            result: DurationResult::new(seconds),
+            warnings: Vec::new(),
+            expression: None,
+            span: None,
+            start: None,
+            end: None,
+            order: None,
+            target_delta: None,
+            bar: None,
+        });
+    }
+
+    let total = rounded_seconds(total_millis);
+    let mut output = ParseOutput {
+        lines,
+        total: DurationResult::new(total),
+        filtered: None,
+        sections: None,
+        remaining: None,
+        rounding_warning: None,
+        totals: None,
+        total_lines: None,
+        adjustments: None,
+        histogram: None,
+        stats: None,
+        warnings: None,
+        rounded_total: None,
+    };
+    output.note_rounding();
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cue(timing: &str) -> String {
+        format!("1\n{}\nHello\n", timing)
+    }
+
+    #[test]
+    fn strict_mode_needs_canonical_timing() {
+        let output = parse_srt(&cue("00:00:01,000 --> 00:00:04,500"), true).unwrap();
+        assert_eq!(output.lines[0].result.seconds, 4);
+
+        for timing in ["0:00:01,000 --> 00:00:04,000", "00:00:01.000 --> 00:00:04,000"] {
+            let err = parse_srt(&cue(timing), true).unwrap_err();
+            assert_eq!(err, format!("Cue 1: malformed cue timing: {}", timing));
+        }
+    }
+
+    #[test]
+    fn tolerant_mode_accepts_missing_hours_and_dot_millis() {
+        let output = parse_srt(&cue("00:01.5 --> 00:04.000"), false).unwrap();
+        assert_eq!(output.lines[0].result.seconds, 3);
+        let output = parse_srt(&cue("0:00:01,000-->0:01:01"), false).unwrap();
+        assert_eq!(output.lines[0].result.seconds, 60);
+
+        // Cues without an index are numbered by position
+        let output = parse_srt("1\n00:00:00,000 --> 00:00:02,000\nA\n\n00:00:02.000 --> 00:00:05.000\nB", false).unwrap();
+        let ids: Vec<usize> = output.lines.iter().map(|line| line.id).collect();
+        assert_eq!(ids, [1, 2]);
+        assert_eq!(output.total.seconds, 5);
+    }
+
+    #[test]
+    fn errors_name_the_offending_cue() {
+        let input = "1\n00:00:00,000 --> 00:00:02,000\nA\n\nB without index";
+        assert_eq!(parse_srt(input, true).unwrap_err(), "Cue 2: missing cue index");
+        assert_eq!(parse_srt(input, false).unwrap_err(), "Cue 2: missing cue index");
+
+        let input = "1\n00:00:00,000 --> 00:00:02,000\nA\n\n7\n00:00:05,000 --> 00:00:03,000\nB";
+        assert_eq!(
+            parse_srt(input, true).unwrap_err(),
+            "Cue 7: invalid cue timing: 00:00:05,000 --> 00:00:03,000 (end time is before start time)"
+        );
+    }
+}
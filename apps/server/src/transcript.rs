@@ -0,0 +1,97 @@
+use nom::{IResult, Parser, branch::alt, bytes::complete::tag, sequence::delimited};
+
+use crate::parser::{DurationResult, LineResult, ParseOutput, Timestamp, format_duration, parse_timestamp};
+
+// "00:12:34" or "[00:12:34]" at the start of a line, followed by a blank or the end
+fn leading_timestamp(line: &str) -> IResult<&str, Timestamp> {
+    let (rest, timestamp) = alt((delimited(tag("["), parse_timestamp, tag("]")), parse_timestamp)).parse(line)?;
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return Err(nom::Err::Error(nom::error::Error::new(line, nom::error::ErrorKind::Space)));
+    }
+    Ok((rest, timestamp))
+}
+
+/// Parses a transcript whose lines start with a timestamp, e.g.
+/// `00:12:34 Alice: ...`, into one line per timestamped line.
+///
+/// Each line lasts until the next line's timestamp. The last line ends at
+/// `end_secs` when given; otherwise it is left open with a zero duration and a
+/// warning, and does not count toward the total. Lines without a leading
+/// timestamp continue the previous line and are skipped.
+pub fn parse_transcript(input: &str, end_secs: Option<u32>) -> Result<ParseOutput, String> {
+    let mut entries: Vec<(usize, u32, &str)> = Vec::new();
+
+    for (index, line) in input.lines().enumerate() {
+        let line = line.trim();
+        let Ok((_, timestamp)) = leading_timestamp(line) else {
+            continue;
+        };
+        timestamp
+            .validate()
+            .map_err(|e| format!("Line {}: {}", index + 1, e))?;
+
+        let start = timestamp.to_seconds();
+        if let Some(&(_, previous, _)) = entries.last()
+            && start < previous
+        {
+            return Err(format!(
+                "Line {}: {} is before the previous line's {}",
+                index + 1,
+                format_duration(start),
+                format_duration(previous)
+            ));
+        }
+        entries.push((index + 1, start, line));
+    }
+
+    let mut lines = Vec::with_capacity(entries.len());
+    let mut total = 0;
+    for (position, &(line_number, start, text)) in entries.iter().enumerate() {
+        let end = match entries.get(position + 1) {
+            Some(&(_, next, _)) => Some(next),
+            None => end_secs,
+        };
+        let (seconds, warnings) = match end {
+            Some(end) if end < start => {
+                return Err(format!(
+                    "Line {}: end time {} is before the line's {}",
+                    line_number,
+                    format_duration(end),
+                    format_duration(start)
+                ));
+            }
+            Some(end) => (end - start, Vec::new()),
+            None => (0, vec!["No end time given; duration left open".to_string()]),
+        };
+        total += seconds;
+        lines.push(LineResult {
+            id: line_number,
+            input: text.to_string(),
+            result: DurationResult::new(seconds),
+            warnings,
+            expression: None,
+            span: end.map(|end| (start, end)),
+            start: None,
+            end: None,
+            order: None,
+            target_delta: None,
+            bar: None,
+        });
+    }
+
+    Ok(ParseOutput {
+        lines,
+        total: DurationResult::new(total),
+        filtered: None,
+        sections: None,
+        remaining: None,
+        rounding_warning: None,
+        totals: None,
+        total_lines: None,
+        adjustments: None,
+        histogram: None,
+        stats: None,
+        warnings: None,
+        rounded_total: None,
+    })
+}